@@ -1,8 +1,7 @@
-use std::path::PathBuf;
 use std::sync::mpsc;
 
-use crate::monitors::Monitor;
-use crate::wallpaper;
+use crate::layouts;
+use crate::wallpaper::{self, Assignment};
 
 enum Msg {
     Status(String),
@@ -24,7 +23,7 @@ impl ApplyJob {
         }
     }
 
-    pub fn start(&mut self, assignments: Vec<(PathBuf, Monitor)>, ctx: &eframe::egui::Context) {
+    pub fn start(&mut self, assignments: Vec<Assignment>, ctx: &eframe::egui::Context) {
         let (tx, rx) = mpsc::channel();
         let ctx = ctx.clone();
         std::thread::spawn(move || {
@@ -35,6 +34,10 @@ impl ApplyJob {
                 log_ctx.request_repaint();
             };
             let result = wallpaper::apply(&assignments, &log);
+            if result.is_ok() {
+                // So `watch` can recompose it when the monitors change.
+                let _ = layouts::remember(&assignments);
+            }
             let _ = tx.send(Msg::Done(result));
             ctx.request_repaint();
         });
@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How the wallpaper reaches the desktop, picked from the session environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// GNOME, Cinnamon and MATE: one image spanned over the logical screen.
+    Gsettings,
+    /// sway: one image per output at its physical resolution.
+    Sway,
+    /// Hyprland with hyprpaper: one image per output at its physical resolution.
+    Hyprpaper,
+}
+
+impl Backend {
+    pub fn detect() -> Self {
+        if std::env::var_os("SWAYSOCK").is_some() {
+            Self::Sway
+        } else if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            Self::Hyprpaper
+        } else {
+            Self::Gsettings
+        }
+    }
+}
+
+/// Show one image per output, given as `(output name, image path)`.
+pub fn set_outputs(backend: Backend, outputs: &[(String, PathBuf)]) -> Result<(), String> {
+    match backend {
+        Backend::Gsettings => Err("gsettings sets a single spanned image".into()),
+        Backend::Sway => {
+            for (output, path) in outputs {
+                run(
+                    "swaymsg",
+                    &["output", output, "bg", &path_str(path), "fill"],
+                )?;
+            }
+            Ok(())
+        }
+        Backend::Hyprpaper => {
+            // hyprpaper keeps preloaded images by path; drop the previous ones
+            // so rewritten files are read again.
+            run("hyprctl", &["hyprpaper", "unload", "all"])?;
+            for (output, path) in outputs {
+                let path = path_str(path);
+                run("hyprctl", &["hyprpaper", "preload", &path])?;
+                run(
+                    "hyprctl",
+                    &["hyprpaper", "wallpaper", &format!("{output},{path}")],
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Run `program` and return its standard output.
+pub fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {program}: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{program} failed: {stderr}"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
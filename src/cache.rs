@@ -3,6 +3,10 @@ use md5::{Digest, Md5};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+/// Part of every cache key; bumped when thumbnails are made differently, so
+/// ones cached before are not used again.
+const VERSION: u32 = 2;
+
 pub fn load(source: &Path) -> Option<egui::ColorImage> {
     let cache = path(source)?;
     if mtime(&cache)? < mtime(source)? {
@@ -16,11 +20,18 @@ pub fn save(source: &Path, thumbnail: &image::DynamicImage) {
     if let Some(parent) = cache.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    let _ = thumbnail.save(&cache);
+    // PNG holds 8 and 16 bits per channel; floating-point TIFFs are narrowed.
+    let _ = match thumbnail {
+        image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => {
+            image::DynamicImage::from(thumbnail.to_rgba8()).save(&cache)
+        }
+        _ => thumbnail.save(&cache),
+    };
 }
 
 fn path(source: &Path) -> Option<PathBuf> {
-    let hash = format!("{:x}", Md5::digest(source.to_string_lossy().as_bytes()));
+    let key = format!("{VERSION}:{}", source.to_string_lossy());
+    let hash = format!("{:x}", Md5::digest(key.as_bytes()));
     let home = std::env::var("HOME").ok()?;
     Some(PathBuf::from(home).join(".cache/nitrohydra").join(format!("{hash}.png")))
 }
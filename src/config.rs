@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Read a RON config file; `None` when it does not exist yet.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+    };
+    ron::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("failed to parse {}: {e}", path.display()))
+}
+
+/// Write a RON config file through a temporary file, so it is never left half-written.
+pub fn write<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+    }
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| format!("failed to serialize {}: {e}", path.display()))?;
+
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, contents)
+        .map_err(|e| format!("failed to write {}: {e}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
+
+/// Refuse a file written in a newer format than `supported`.
+pub fn check_version(path: &Path, version: u32, supported: u32) -> Result<(), String> {
+    if version > supported {
+        return Err(format!(
+            "{} was written by a newer version of nitrohydra",
+            path.display()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::backend::Backend;
+use crate::slideshow::Source;
+use crate::{monitors, random, wallpaper};
+
+/// Length of the cross-fade between two images, in seconds.
+const TRANSITION_SECS: f64 = 5.0;
+
+pub struct Options {
+    pub source: Source,
+    pub count: usize,
+    pub duration_minutes: u64,
+    pub prefer_fit: bool,
+}
+
+/// Compose `count` spanned wallpapers, write them as a GNOME background XML
+/// slideshow and set it as the desktop background. Only desktops set through
+/// gsettings play such slideshows.
+pub fn run(options: &Options) -> Result<PathBuf, String> {
+    let desktop = match Backend::detect() {
+        Backend::Gsettings => None,
+        Backend::Sway => Some("sway"),
+        Backend::Hyprpaper => Some("Hyprland"),
+    };
+    if let Some(desktop) = desktop {
+        return Err(format!(
+            "{desktop} cannot play XML slideshows; use 'nitrohydra slideshow' instead"
+        ));
+    }
+    let images = options.source.images()?;
+    let monitors = monitors::detect()?;
+
+    let dir = wallpaper::dirs_data().join("nitrohydra/slideshow");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+
+    // The desktop caches images by path, so every export gets fresh file names.
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut used = Vec::new();
+    let mut files = Vec::new();
+    for i in 0..options.count {
+        let assignments = random::pick_from_paths(&images, &monitors, &used, options.prefer_fit);
+        used.extend(assignments.iter().map(|a| a.path.clone()));
+
+        let log = |msg: &str| eprintln!("[{}/{}] {msg}", i + 1, options.count);
+        let composed = wallpaper::compose(&assignments, &log)?;
+        let file = dir.join(format!("{stamp}-{i:03}.jpg"));
+        wallpaper::save_jpeg(&composed.to_rgb8(), &file)?;
+        files.push(file);
+    }
+
+    let xml_path = dir.join("slideshow.xml");
+    let duration = (options.duration_minutes * 60) as f64 - TRANSITION_SECS;
+    std::fs::write(&xml_path, background_xml(&files, duration))
+        .map_err(|e| format!("failed to write {}: {e}", xml_path.display()))?;
+
+    eprintln!("Setting wallpaper…");
+    wallpaper::set_wallpaper(&xml_path)?;
+    // Only now is the previous slideshow no longer shown.
+    remove_previous(&dir, &files);
+    Ok(xml_path)
+}
+
+/// Remove images of earlier exports, including ones left by an export that
+/// failed halfway, keeping `files`.
+fn remove_previous(dir: &Path, files: &[PathBuf]) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension().is_some_and(|ext| ext == "jpg") && !files.contains(&path) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A looping `<background>` with every image shown for `duration` seconds,
+/// followed by a cross-fade to the next one.
+fn background_xml(files: &[PathBuf], duration: f64) -> String {
+    let mut xml = String::from("<background>\n");
+    xml.push_str("  <starttime>\n");
+    xml.push_str("    <year>2000</year><month>1</month><day>1</day>\n");
+    xml.push_str("    <hour>0</hour><minute>0</minute><second>0</second>\n");
+    xml.push_str("  </starttime>\n");
+    for (i, file) in files.iter().enumerate() {
+        let from = escape(&file.to_string_lossy());
+        let to = escape(&files[(i + 1) % files.len()].to_string_lossy());
+        let _ = writeln!(
+            xml,
+            "  <static>\n    <duration>{duration:.1}</duration>\n    <file>{from}</file>\n  </static>"
+        );
+        if files.len() > 1 {
+            let _ = writeln!(
+                xml,
+                "  <transition type=\"overlay\">\n    <duration>{TRANSITION_SECS:.1}</duration>\n    <from>{from}</from>\n    <to>{to}</to>\n  </transition>"
+            );
+        }
+    }
+    xml.push_str("</background>\n");
+    xml
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml_loops_back_to_first_image() {
+        let files = [PathBuf::from("/w/a.jpg"), PathBuf::from("/w/b&c.jpg")];
+        let xml = background_xml(&files, 595.0);
+        assert!(xml.contains("<file>/w/b&amp;c.jpg</file>"));
+        assert!(xml.contains("<from>/w/b&amp;c.jpg</from>\n    <to>/w/a.jpg</to>"));
+        assert_eq!(xml.matches("<static>").count(), 2);
+        assert_eq!(xml.matches("<transition").count(), 2);
+    }
+
+    #[test]
+    fn remove_previous_keeps_the_new_images() {
+        let dir = std::env::temp_dir().join(format!("nitrohydra-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let [old, new, xml] = ["1-000.jpg", "2-000.jpg", "slideshow.xml"].map(|f| dir.join(f));
+        for file in [&old, &new, &xml] {
+            std::fs::write(file, "").unwrap();
+        }
+
+        remove_previous(&dir, std::slice::from_ref(&new));
+        assert!(!old.exists());
+        assert!(new.exists() && xml.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn xml_single_image_has_no_transition() {
+        let xml = background_xml(&[PathBuf::from("/w/a.jpg")], 60.0);
+        assert!(!xml.contains("<transition"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::Path;
+
+use crate::monitors::Monitor;
+
+/// Width to height ratio from which an image counts as ultrawide (e.g. 21:9).
+const ULTRAWIDE_RATIO: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Wider than tall (or square), but short of ultrawide.
+    Landscape,
+    Portrait,
+    Ultrawide,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 3] = [
+        Orientation::Landscape,
+        Orientation::Portrait,
+        Orientation::Ultrawide,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Orientation::Landscape => "Landscape",
+            Orientation::Portrait => "Portrait",
+            Orientation::Ultrawide => "Ultrawide",
+        }
+    }
+
+    fn of([w, h]: [u32; 2]) -> Self {
+        if h > w {
+            Orientation::Portrait
+        } else if f64::from(w) / f64::from(h.max(1)) >= ULTRAWIDE_RATIO {
+            Orientation::Ultrawide
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+/// Narrows the gallery by name and by the metadata of loaded images.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    /// Part of the file name, or a glob with `*` and `?`; case-insensitive.
+    pub query: String,
+    pub min_width: u32,
+    pub min_height: u32,
+    pub orientation: Option<Orientation>,
+    /// Index of a monitor whose physical resolution images must cover.
+    pub monitor: Option<usize>,
+    /// Lowercase file extension, e.g. `png`.
+    pub file_type: Option<String>,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Whether the image at `path` with pixel size `size` passes every filter.
+    pub fn matches(&self, path: &Path, size: [u32; 2], monitors: &[Monitor]) -> bool {
+        let [w, h] = size;
+        if w < self.min_width || h < self.min_height {
+            return false;
+        }
+        if self.orientation.is_some_and(|o| o != Orientation::of(size)) {
+            return false;
+        }
+        if let Some(monitor) = self.monitor.and_then(|i| monitors.get(i)) {
+            let (mw, mh) = monitor.physical_size();
+            if w < mw || h < mh {
+                return false;
+            }
+        }
+        if let Some(file_type) = &self.file_type
+            && file_type_of(path).as_ref() != Some(file_type)
+        {
+            return false;
+        }
+        self.matches_name(path)
+    }
+
+    fn matches_name(&self, path: &Path) -> bool {
+        let query = self.query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if query.contains(['*', '?']) {
+            glob_match(&query, &name)
+        } else {
+            name.contains(&query)
+        }
+    }
+}
+
+/// The lowercase extension of `path`, as offered by the file type filter.
+pub fn file_type_of(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    Some(match ext.as_str() {
+        "jpeg" => "jpg".into(),
+        "tif" => "tiff".into(),
+        _ => ext,
+    })
+}
+
+/// Match `text` against `pattern`, where `*` stands for any run of characters
+/// and `?` for exactly one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if the rest fails to match.
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((sp, st)) = star {
+            p = sp + 1;
+            t = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("*.png", "sunset.png"));
+        assert!(glob_match("img_??.*", "img_07.jpg"));
+        assert!(!glob_match("img_??.*", "img_7.jpg"));
+        assert!(glob_match("*snow*", "winter-snow-2.jpg"));
+        assert!(!glob_match("*.png", "sunset.jpg"));
+    }
+
+    #[test]
+    fn name_query_is_substring_or_glob() {
+        let filter = Filter {
+            query: "Snow".into(),
+            ..Filter::default()
+        };
+        assert!(filter.matches(Path::new("/w/winter-snow.jpg"), [10, 10], &[]));
+        assert!(!filter.matches(Path::new("/w/snow/beach.jpg"), [10, 10], &[]));
+
+        let filter = Filter {
+            query: "*.PNG".into(),
+            ..Filter::default()
+        };
+        assert!(filter.matches(Path::new("/w/a.png"), [10, 10], &[]));
+        assert!(!filter.matches(Path::new("/w/a.png.jpg"), [10, 10], &[]));
+    }
+
+    #[test]
+    fn orientation_and_resolution() {
+        assert_eq!(Orientation::of([1920, 1080]), Orientation::Landscape);
+        assert_eq!(Orientation::of([1080, 1920]), Orientation::Portrait);
+        assert_eq!(Orientation::of([3440, 1440]), Orientation::Ultrawide);
+
+        let filter = Filter {
+            monitor: Some(0),
+            ..Filter::default()
+        };
+        let monitors = [Monitor {
+            scale: 2.0,
+            ..Monitor::test("DP-1", 1920, 1080, 0)
+        }];
+        let path = Path::new("/w/a.jpg");
+        assert!(!filter.matches(path, [2560, 1440], &monitors));
+        assert!(filter.matches(path, [3840, 2160], &monitors));
+
+        let filter = Filter {
+            min_width: 2000,
+            file_type: Some("jpg".into()),
+            ..Filter::default()
+        };
+        assert!(filter.matches(Path::new("/w/a.JPEG"), [2560, 1440], &[]));
+        assert!(!filter.matches(Path::new("/w/a.png"), [2560, 1440], &[]));
+        assert!(!filter.matches(path, [1920, 1080], &[]));
+    }
+}
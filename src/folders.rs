@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+/// A folder of the library and the subfolders that contain images.
+#[derive(Debug)]
+pub struct Folder {
+    pub name: String,
+    pub path: PathBuf,
+    /// Images in this folder and all folders below it.
+    pub count: usize,
+    /// Subfolders, sorted by name.
+    pub children: Vec<Folder>,
+}
+
+impl Folder {
+    pub fn new(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+        Self {
+            name,
+            path: path.to_path_buf(),
+            count: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Count `image` in this folder and every subfolder on its way.
+    pub fn insert(&mut self, image: &Path) {
+        let Some(relative) = image
+            .parent()
+            .and_then(|dir| dir.strip_prefix(&self.path).ok())
+        else {
+            return;
+        };
+
+        let mut folder = self;
+        folder.count += 1;
+        for component in relative {
+            let name = component.to_string_lossy();
+            let i = match folder
+                .children
+                .binary_search_by(|c| c.name.as_str().cmp(&name))
+            {
+                Ok(i) => i,
+                Err(i) => {
+                    let child = Folder::new(&folder.path.join(component));
+                    folder.children.insert(i, child);
+                    i
+                }
+            };
+            folder = &mut folder.children[i];
+            folder.count += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_images_along_the_path() {
+        let mut root = Folder::new(Path::new("/w"));
+        for image in [
+            "/w/a.jpg",
+            "/w/winter/b.jpg",
+            "/w/art/c.jpg",
+            "/w/winter/snow/d.jpg",
+        ] {
+            root.insert(Path::new(image));
+        }
+        root.insert(Path::new("/elsewhere/e.jpg"));
+
+        assert_eq!(root.count, 4);
+        let names: Vec<_> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["art", "winter"]);
+        let winter = &root.children[1];
+        assert_eq!(winter.count, 2);
+        assert_eq!(winter.children[0].path, Path::new("/w/winter/snow"));
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use eframe::egui;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::loader::{self, ImageInfo};
+
+/// Files are often written in several steps; wait for a quiet moment before reading them.
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+pub enum Change {
+    /// An image was added or rewritten.
+    Image(ImageInfo, egui::ColorImage),
+    /// An image could not be read, and why.
+    Failed(PathBuf, String),
+    /// A file or folder is gone.
+    Removed(PathBuf),
+}
+
+/// Watches library roots with inotify and reports image changes below them.
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+    rx: mpsc::Receiver<Change>,
+}
+
+impl LibraryWatcher {
+    pub fn start(roots: Vec<PathBuf>, ctx: egui::Context) -> Result<Self, String> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(event_tx)
+            .map_err(|e| format!("failed to watch library: {e}"))?;
+        for root in &roots {
+            watcher
+                .watch(root, RecursiveMode::Recursive)
+                .map_err(|e| format!("failed to watch {}: {e}", root.display()))?;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            while let Some(paths) = next_batch(&event_rx) {
+                for path in paths {
+                    if report(&roots, &path, &tx).is_err() {
+                        return; // the gallery went away
+                    }
+                }
+                ctx.request_repaint();
+            }
+        });
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    pub fn poll(&self) -> Option<Change> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// Collect the paths of a burst of events; `None` once the watcher is dropped.
+fn next_batch(events: &mpsc::Receiver<notify::Result<notify::Event>>) -> Option<BTreeSet<PathBuf>> {
+    let mut paths = BTreeSet::new();
+    let mut add = |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && !matches!(event.kind, EventKind::Access(_) | EventKind::Other)
+        {
+            paths.extend(event.paths);
+        }
+    };
+    add(events.recv().ok()?);
+    loop {
+        match events.recv_timeout(SETTLE_DELAY) {
+            Ok(event) => add(event),
+            Err(mpsc::RecvTimeoutError::Timeout) => return Some(paths),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+/// Report the current state of `path`: gone, or (re)loaded images.
+fn report(roots: &[PathBuf], path: &Path, tx: &mpsc::Sender<Change>) -> Result<(), ()> {
+    let Some(root) = roots.iter().position(|r| loader::in_library(r, path)) else {
+        return Ok(());
+    };
+    if !path.exists() {
+        return tx.send(Change::Removed(path.to_path_buf())).map_err(|_| ());
+    }
+
+    // A folder moved into the library brings its images along.
+    let images = if path.is_dir() {
+        loader::scan(path).unwrap_or_default()
+    } else if loader::is_image(path) {
+        vec![path.to_path_buf()]
+    } else {
+        return Ok(());
+    };
+    for image in images {
+        let change = match loader::info(root, &image)
+            .and_then(|info| Ok((info, loader::thumbnail(&image)?)))
+        {
+            Ok((info, thumbnail)) => Change::Image(info, thumbnail),
+            Err(e) => Change::Failed(image, e.to_string()),
+        };
+        tx.send(change).map_err(|_| ())?;
+    }
+    Ok(())
+}
@@ -1,8 +1,16 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use eframe::egui;
+use serde::{Deserialize, Serialize};
 
-use crate::loader::{ImageLoader, Poll};
+use crate::folders::Folder;
+use crate::fswatch::{Change, LibraryWatcher};
+use crate::loader::{ImageInfo, ImageLoader, Poll};
+use crate::monitors::Monitor;
+use crate::sort::{SortOrder, Sorter};
+use crate::textures::Textures;
 
 pub enum State {
     Empty,
@@ -10,25 +18,37 @@ pub enum State {
     Loaded(Vec<ImageEntry>),
 }
 
+/// A library folder; disabled ones stay in the list but are not loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Root {
+    pub path: String,
+    pub enabled: bool,
+}
+
+/// An image of the gallery; its thumbnail is kept apart, see `Gallery::texture`.
+#[derive(Clone)]
 pub struct ImageEntry {
-    pub texture: egui::TextureHandle,
+    /// Where the image was found; identifies it across reloads.
+    pub path: PathBuf,
+    /// Index of the loaded root the image was found in.
+    pub root: usize,
     pub original_size: [u32; 2],
     pub modified: SystemTime,
-}
-
-impl Clone for ImageEntry {
-    fn clone(&self) -> Self {
-        Self {
-            texture: self.texture.clone(),
-            original_size: self.original_size,
-            modified: self.modified,
-        }
-    }
+    pub file_size: u64,
 }
 
 pub struct Gallery {
     state: State,
     loader: Option<ImageLoader>,
+    /// Whether the loader is still reporting images.
+    scanning: bool,
+    textures: Textures,
+    /// Files that could not be read, with the reason.
+    failures: BTreeMap<PathBuf, String>,
+    watcher: Option<LibraryWatcher>,
+    roots: Vec<PathBuf>,
+    folders: Vec<Folder>,
+    sorter: Sorter,
 }
 
 impl Gallery {
@@ -36,57 +56,222 @@ impl Gallery {
         Self {
             state: State::Empty,
             loader: None,
+            scanning: false,
+            textures: Textures::new(),
+            failures: BTreeMap::new(),
+            watcher: None,
+            roots: Vec::new(),
+            folders: Vec::new(),
+            sorter: Sorter {
+                order: SortOrder::default(),
+                monitors: Vec::new(),
+            },
         }
     }
 
-    pub fn load(&mut self, path: &str, ctx: &egui::Context) {
-        self.loader = Some(ImageLoader::start(path.to_string(), ctx.clone()));
+    /// Load the images below all of `roots` into one gallery, and keep it up
+    /// to date as files change.
+    pub fn load(&mut self, roots: &[PathBuf], ctx: &egui::Context) {
+        self.roots = roots.to_vec();
+        self.folders = roots.iter().map(|r| Folder::new(r)).collect();
+        self.textures.clear();
+        self.failures.clear();
+        if roots.is_empty() {
+            self.loader = None;
+            self.watcher = None;
+            self.state = State::Empty;
+            return;
+        }
+        // Without live updates, Reload still picks up changes.
+        self.watcher = LibraryWatcher::start(roots.to_vec(), ctx.clone()).ok();
+        self.loader = Some(ImageLoader::start(roots.to_vec(), ctx.clone()));
+        self.scanning = true;
         self.state = State::Loaded(vec![]);
     }
 
-    pub fn poll(&mut self, ctx: &egui::Context) {
-        let Some(loader) = &self.loader else { return };
-        loop {
+    /// Take in loaded and changed images, once per frame. Returns whether
+    /// entries were added, removed or reordered, or the scan finished.
+    pub fn poll(&mut self, ctx: &egui::Context) -> bool {
+        self.textures.next_frame();
+        let mut changed = false;
+        while let Some(loader) = &self.loader {
             match loader.poll() {
-                Poll::Image(modified, name, image, dimensions) => {
-                    let texture = ctx.load_texture(name, image, Default::default());
-                    let entry = ImageEntry {
-                        texture,
-                        original_size: dimensions,
-                        modified,
-                    };
-                    if let State::Loaded(v) = &mut self.state {
-                        v.push(entry);
-                        v.sort_by(|a, b| b.modified.cmp(&a.modified));
-                    } else {
-                        self.state = State::Loaded(vec![entry]);
+                Poll::Info(info) => {
+                    self.insert(ctx, info, None);
+                    changed = true;
+                }
+                Poll::Scanned => {
+                    self.scanning = false;
+                    changed = true;
+                }
+                Poll::Thumbnail {
+                    path,
+                    image,
+                    requested,
+                } => {
+                    // Thumbnails out of view only fill up free room.
+                    if (requested || !self.textures.is_full()) && self.find(&path).is_some() {
+                        self.textures.insert(ctx, &path, image);
                     }
                 }
+                Poll::Failed(path, e) => {
+                    self.failures.insert(path, e);
+                }
                 Poll::Error(e) => {
                     self.state = State::Error(e);
                     self.loader = None;
+                    self.scanning = false;
                     break;
                 }
                 Poll::Pending => break,
-                Poll::Done => {
-                    if !matches!(&self.state, State::Loaded(_)) {
-                        self.state = State::Loaded(vec![]);
-                    }
-                    self.loader = None;
-                    break;
+            }
+        }
+
+        while let Some(change) = self.watcher.as_ref().and_then(|w| w.poll()) {
+            match change {
+                Change::Image(info, image) => {
+                    self.failures.remove(&info.path);
+                    self.insert(ctx, info, Some(image));
+                }
+                Change::Failed(path, e) => {
+                    self.failures.insert(path, e);
+                }
+                Change::Removed(path) => self.remove(&path),
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Add an image in sort order, or refresh it if it is already in the gallery.
+    fn insert(&mut self, ctx: &egui::Context, info: ImageInfo, image: Option<egui::ColorImage>) {
+        let State::Loaded(entries) = &mut self.state else {
+            return;
+        };
+        let entry = match entries.iter().position(|e| e.path == info.path) {
+            Some(i) => {
+                // The new metadata may move it elsewhere.
+                let mut entry = entries.remove(i);
+                entry.original_size = info.dimensions;
+                entry.modified = info.modified;
+                entry.file_size = info.file_size;
+                entry
+            }
+            None => {
+                if let Some(folder) = self.folders.get_mut(info.root) {
+                    folder.insert(&info.path);
                 }
+                ImageEntry {
+                    path: info.path,
+                    root: info.root,
+                    original_size: info.dimensions,
+                    modified: info.modified,
+                    file_size: info.file_size,
+                }
+            }
+        };
+        if let Some(image) = image {
+            self.textures.insert(ctx, &entry.path, image);
+        }
+        let i = self.sorter.position(entries, &entry);
+        entries.insert(i, entry);
+    }
+
+    /// The thumbnail of the image at `path`, if it is in memory; call
+    /// `request_thumbnails` for the ones that are not.
+    pub fn texture(&self, path: &Path) -> Option<&egui::TextureHandle> {
+        self.textures.get(path)
+    }
+
+    /// Of `paths`, the ones whose thumbnail is not in memory, each once and in
+    /// order. The ones in memory are kept for this frame.
+    pub fn missing_thumbnails<'a>(
+        &self,
+        paths: impl IntoIterator<Item = &'a Path>,
+    ) -> Vec<PathBuf> {
+        let mut missing: Vec<PathBuf> = Vec::new();
+        for path in paths {
+            if !self.textures.keep(path) && !missing.iter().any(|p| p == path) {
+                missing.push(path.to_path_buf());
             }
         }
+        missing
     }
 
+    /// Decode the thumbnails of `paths` first, e.g. the ones scrolled into view.
+    /// Replaces the paths of the previous call.
+    pub fn request_thumbnails(&self, paths: Vec<PathBuf>) {
+        if let Some(loader) = &self.loader {
+            loader.request(paths);
+        }
+    }
+
+    pub fn sort_order(&self) -> SortOrder {
+        self.sorter.order
+    }
+
+    pub fn set_sort_order(&mut self, order: SortOrder) {
+        self.sorter.order = order;
+        self.resort();
+    }
+
+    /// Monitors that `SortKey::Fit` measures images against.
+    pub fn set_monitors(&mut self, monitors: Vec<Monitor>) {
+        self.sorter.monitors = monitors;
+        self.resort();
+    }
+
+    fn resort(&mut self) {
+        if let State::Loaded(entries) = &mut self.state {
+            entries.sort_by(|a, b| self.sorter.compare(a, b));
+        }
+    }
+
+    /// Drop the image at `path`, or every image below it for a folder.
+    fn remove(&mut self, path: &Path) {
+        let State::Loaded(entries) = &mut self.state else {
+            return;
+        };
+        entries.retain(|e| !e.path.starts_with(path));
+        self.textures.remove(path);
+        self.failures.retain(|p, _| !p.starts_with(path));
+
+        self.folders = self.roots.iter().map(|r| Folder::new(r)).collect();
+        for entry in entries.iter() {
+            if let Some(folder) = self.folders.get_mut(entry.root) {
+                folder.insert(&entry.path);
+            }
+        }
+    }
+
+    /// Whether images are still being found; the entries are not complete yet.
+    pub fn is_scanning(&self) -> bool {
+        self.scanning
+    }
+
+    /// Whether images are being found or thumbnails decoded.
     pub fn is_loading(&self) -> bool {
-        self.loader.is_some()
+        self.scanning || self.loader.as_ref().is_some_and(|l| l.is_busy())
     }
 
     pub fn state(&self) -> &State {
         &self.state
     }
 
+    /// Folders of the loaded images, one tree per loaded root.
+    pub fn folders(&self) -> &[Folder] {
+        &self.folders
+    }
+
+    /// Files that could not be read, by path, with the reason.
+    pub fn failures(&self) -> &BTreeMap<PathBuf, String> {
+        &self.failures
+    }
+
+    pub fn find(&self, path: &Path) -> Option<&ImageEntry> {
+        self.entries()?.iter().find(|e| e.path == path)
+    }
+
     pub fn entries(&self) -> Option<&[ImageEntry]> {
         match &self.state {
             State::Loaded(entries) => Some(entries),
@@ -94,3 +279,23 @@ impl Gallery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_thumbnails_merges_selection_and_view() {
+        let ctx = egui::Context::default();
+        let mut gallery = Gallery::new();
+        let image = egui::ColorImage::new([4, 4], egui::Color32::BLACK);
+        gallery.textures.insert(&ctx, Path::new("/w/a.jpg"), image);
+
+        // A selected image scrolled far away, then the grid cells in view.
+        let selected = [PathBuf::from("/w/z.jpg")];
+        let in_view = ["/w/a.jpg", "/w/b.jpg", "/w/z.jpg"].map(PathBuf::from);
+        let missing =
+            gallery.missing_thumbnails(selected.iter().chain(&in_view).map(PathBuf::as_path));
+        assert_eq!(missing, ["/w/z.jpg", "/w/b.jpg"].map(PathBuf::from));
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many of the latest applied images count as "recently used".
+const RECENT_COUNT: usize = 20;
+/// How many lines the history file keeps; older ones are dropped on each write.
+const MAX_LINES: usize = 500;
+
+/// Add applied images to the history file, one `<unix time>\t<path>` line
+/// each, keeping only its last `MAX_LINES` lines. Paths are stored canonical,
+/// like the gallery's, so they compare equal however they were given.
+pub fn record(paths: &[&Path]) {
+    let file = path();
+    if let Some(parent) = file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let contents = std::fs::read_to_string(&file).unwrap_or_default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| p.canonicalize().unwrap_or_else(|_| p.to_path_buf()))
+        .collect();
+
+    let tmp_path = file.with_extension("tmp");
+    if std::fs::write(&tmp_path, append(&contents, now, &paths)).is_ok() {
+        let _ = std::fs::rename(&tmp_path, &file);
+    }
+}
+
+/// `contents` with a line for each of `paths` added, cut to the last `MAX_LINES`.
+fn append(contents: &str, now: u64, paths: &[PathBuf]) -> String {
+    let added: Vec<String> = paths
+        .iter()
+        .map(|path| format!("{now}\t{}", path.display()))
+        .collect();
+    let lines: Vec<&str> = contents
+        .lines()
+        .chain(added.iter().map(String::as_str))
+        .collect();
+    let mut out = lines[lines.len().saturating_sub(MAX_LINES)..].join("\n");
+    out.push('\n');
+    out
+}
+
+/// Recently applied images, newest first, without duplicates.
+pub fn recent() -> Vec<PathBuf> {
+    let Ok(contents) = std::fs::read_to_string(path()) else {
+        return Vec::new();
+    };
+    let mut recent: Vec<PathBuf> = Vec::new();
+    for line in contents.lines().rev() {
+        let Some((_, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let path = PathBuf::from(path);
+        if !recent.contains(&path) {
+            recent.push(path);
+        }
+        if recent.len() == RECENT_COUNT {
+            break;
+        }
+    }
+    recent
+}
+
+fn path() -> PathBuf {
+    crate::wallpaper::dirs_data().join("nitrohydra/history")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_keeps_the_last_lines() {
+        let contents = append("", 1, &[PathBuf::from("/w/a.jpg")]);
+        assert_eq!(contents, "1\t/w/a.jpg\n");
+
+        let mut contents = String::new();
+        for i in 0..MAX_LINES + 10 {
+            contents = append(&contents, i as u64, &[PathBuf::from(format!("/w/{i}.jpg"))]);
+        }
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), MAX_LINES);
+        assert_eq!(lines[0], "10\t/w/10.jpg");
+        assert_eq!(
+            lines[MAX_LINES - 1],
+            format!("{0}\t/w/{0}.jpg", MAX_LINES + 9)
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::monitors::{self, Monitor};
+use crate::profiles::{self, Slot};
+use crate::wallpaper::{self, Assignment};
+
+/// Version of the layouts file format, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// The images last applied with one monitor setup.
+#[derive(Serialize, Deserialize)]
+struct Layout {
+    fingerprint: String,
+    slots: Vec<Slot>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayoutsFile {
+    version: u32,
+    layouts: Vec<Layout>,
+}
+
+impl LayoutsFile {
+    /// Keep `assignments` for the setup of `monitors`, replacing what was kept for it.
+    fn remember(&mut self, monitors: &[Monitor], assignments: &[Assignment]) {
+        let fingerprint = monitors::fingerprint(monitors);
+        self.layouts.retain(|l| l.fingerprint != fingerprint);
+        self.layouts.push(Layout {
+            fingerprint,
+            slots: profiles::slots(assignments),
+        });
+    }
+
+    fn matching(&self, monitors: &[Monitor]) -> Option<Vec<Assignment>> {
+        let fingerprint = monitors::fingerprint(monitors);
+        self.layouts
+            .iter()
+            .find(|l| l.fingerprint == fingerprint)
+            .map(|l| profiles::assign(&l.slots, monitors))
+    }
+}
+
+/// Remember `assignments` for the monitors connected right now. The whole
+/// setup counts, also the monitors that got no image.
+pub fn remember(assignments: &[Assignment]) -> Result<(), String> {
+    let monitors = monitors::detect()?;
+    let mut file = load()?;
+    file.remember(&monitors, assignments);
+    config::write(&path(), &file)
+}
+
+/// The assignment remembered for `monitors`, placed onto them.
+pub fn matching(monitors: &[Monitor]) -> Result<Option<Vec<Assignment>>, String> {
+    Ok(load()?.matching(monitors))
+}
+
+/// Like `matching`, but falls back to the most recently applied images of any
+/// other setup, recomposed onto `monitors`.
+pub fn for_monitors(monitors: &[Monitor]) -> Result<Option<Vec<Assignment>>, String> {
+    let file = load()?;
+    if let Some(assignments) = file.matching(monitors) {
+        return Ok(Some(assignments));
+    }
+    Ok(file
+        .layouts
+        .last()
+        .map(|l| profiles::assign(&l.slots, monitors)))
+}
+
+fn load() -> Result<LayoutsFile, String> {
+    let path = path();
+    let Some(file) = config::read::<LayoutsFile>(&path)? else {
+        return Ok(LayoutsFile {
+            version: VERSION,
+            layouts: Vec::new(),
+        });
+    };
+    config::check_version(&path, file.version, VERSION)?;
+    Ok(file)
+}
+
+fn path() -> PathBuf {
+    wallpaper::dirs_config().join("nitrohydra/layouts.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file() -> LayoutsFile {
+        LayoutsFile {
+            version: VERSION,
+            layouts: Vec::new(),
+        }
+    }
+
+    fn paths(assignments: &[Assignment]) -> Vec<(&str, &str)> {
+        assignments
+            .iter()
+            .map(|a| (a.monitor.name.as_str(), a.path.to_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn remembers_per_setup() {
+        let laptop = [Monitor::test("eDP-1", 1920, 1080, 0)];
+        let docked = [
+            Monitor::test("DP-4", 2560, 1440, 0),
+            Monitor::test("HDMI-0", 2560, 1440, 2560),
+        ];
+        let mut file = file();
+        file.remember(
+            &laptop,
+            &[Assignment::new("/a.jpg".into(), laptop[0].clone())],
+        );
+        let docked_images = [
+            Assignment::new("/b.jpg".into(), docked[0].clone()),
+            Assignment::new("/c.jpg".into(), docked[1].clone()),
+        ];
+        file.remember(&docked, &docked_images);
+
+        let restored = file.matching(&laptop).unwrap();
+        assert_eq!(paths(&restored), [("eDP-1", "/a.jpg")]);
+        let restored = file.matching(&docked).unwrap();
+        assert_eq!(paths(&restored), [("DP-4", "/b.jpg"), ("HDMI-0", "/c.jpg")]);
+
+        // Applying again with the same setup replaces what was kept.
+        file.remember(
+            &laptop,
+            &[Assignment::new("/d.jpg".into(), laptop[0].clone())],
+        );
+        assert_eq!(file.layouts.len(), 2);
+        assert_eq!(
+            paths(&file.matching(&laptop).unwrap()),
+            [("eDP-1", "/d.jpg")]
+        );
+    }
+
+    #[test]
+    fn matches_when_only_some_monitors_were_assigned() {
+        let office = [
+            Monitor::test("DP-1", 1920, 1080, 0),
+            Monitor::test("DP-2", 1920, 1080, 1920),
+            Monitor::test("DP-3", 1920, 1080, 3840),
+        ];
+        let mut file = file();
+        let assignments = [
+            Assignment::new("/a.jpg".into(), office[0].clone()),
+            Assignment::new("/b.jpg".into(), office[1].clone()),
+        ];
+        file.remember(&office, &assignments);
+
+        let restored = file.matching(&office).unwrap();
+        assert_eq!(paths(&restored), [("DP-1", "/a.jpg"), ("DP-2", "/b.jpg")]);
+        assert!(file.matching(&office[..2]).is_none());
+    }
+}
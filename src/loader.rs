@@ -1,109 +1,569 @@
-use crate::cache;
+use crate::{cache, svg};
 use eframe::egui;
+use image::metadata::Orientation;
+use image::ImageDecoder;
 use rayon::prelude::*;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{mpsc, Arc};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::time::SystemTime;
 
-const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png"];
+/// Extensions of files the library lists; the format of raster images is read from the content.
+/// AVIF is listed in every build, but decodes only with the `avif` feature.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "bmp", "tif", "tiff", "gif", "qoi", "avif", "svg", "svgz",
+];
 const MAX_TEXTURE_SIZE: u32 = 512;
+/// How many levels of subfolders `scan` descends into.
+const MAX_DEPTH: usize = 8;
 
-pub struct LoadResult {
-    modified: SystemTime,
-    name: String,
-    image: egui::ColorImage,
-    dimensions: [u32; 2],
+/// What the gallery knows about an image before its thumbnail is decoded.
+pub struct ImageInfo {
+    /// Index of the root the image was found below.
+    pub root: usize,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub file_size: u64,
+    pub dimensions: [u32; 2],
 }
 
 pub enum Poll {
-    Image(SystemTime, String, egui::ColorImage, [u32; 2]),
+    /// An image was found; its thumbnail follows later.
+    Info(ImageInfo),
+    /// Every image has been reported.
+    Scanned,
+    Thumbnail {
+        path: PathBuf,
+        image: egui::ColorImage,
+        /// Whether it was asked for with `request`, rather than decoded in the background.
+        requested: bool,
+    },
+    /// A file that could not be read, and why.
+    Failed(PathBuf, String),
+    /// The library could not be read at all.
     Error(String),
     Pending,
-    Done,
 }
 
-type LoadResultPayload = Result<LoadResult, String>;
+/// Thumbnails still to decode; the ones asked for by `request` go first.
+#[derive(Default)]
+struct Queue {
+    remaining: HashSet<PathBuf>,
+    /// The rest, popped from the back.
+    pending: Vec<PathBuf>,
+    /// Popped from the back, so the first path asked for is at the end.
+    priority: Vec<PathBuf>,
+    in_flight: HashSet<PathBuf>,
+    /// Files that failed to decode, not to be tried again.
+    failed: HashSet<PathBuf>,
+}
+
+impl Queue {
+    /// Add `path` to the background work unless it is already on its way.
+    fn add(&mut self, path: PathBuf) {
+        if !self.in_flight.contains(&path)
+            && !self.failed.contains(&path)
+            && self.remaining.insert(path.clone())
+        {
+            self.pending.push(path);
+        }
+    }
 
+    /// The next path to decode, and whether it was asked for.
+    fn next(&mut self) -> Option<(PathBuf, bool)> {
+        loop {
+            let (path, requested) = match self.priority.pop() {
+                Some(path) => (path, true),
+                None => (self.pending.pop()?, false),
+            };
+            if self.remaining.remove(&path) {
+                self.in_flight.insert(path.clone());
+                return Some((path, requested));
+            }
+        }
+    }
+}
+
+type Shared = (Mutex<Queue>, Condvar);
+
+/// Scans the library and decodes thumbnails for as long as it lives, so
+/// thumbnails dropped from memory can be read again.
 pub struct ImageLoader {
-    rx: mpsc::Receiver<LoadResultPayload>,
+    rx: mpsc::Receiver<Poll>,
+    queue: Arc<Shared>,
     cancelled: Arc<AtomicBool>,
 }
 
 impl Drop for ImageLoader {
     fn drop(&mut self) {
+        // Under the lock, so no worker misses the wake-up between its check and its wait.
+        let _queue = self.queue.0.lock();
         self.cancelled.store(true, Ordering::Relaxed);
+        self.queue.1.notify_all();
     }
 }
 
 impl ImageLoader {
-    pub fn start(path: String, ctx: egui::Context) -> Self {
+    /// Scan `roots`, report every image with its metadata, then decode the thumbnails.
+    pub fn start(roots: Vec<PathBuf>, ctx: egui::Context) -> Self {
         let (tx, rx) = mpsc::sync_channel(32);
+        let queue = Arc::new(Shared::default());
         let cancelled = Arc::new(AtomicBool::new(false));
+
+        let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+        for _ in 0..workers {
+            let (tx, ctx) = (tx.clone(), ctx.clone());
+            let (queue, cancelled) = (Arc::clone(&queue), Arc::clone(&cancelled));
+            std::thread::spawn(move || decode_thumbnails(&tx, &ctx, &queue, &cancelled));
+        }
+        let queue_clone = Arc::clone(&queue);
         let cancelled_clone = Arc::clone(&cancelled);
         std::thread::spawn(move || {
-            decode(&path, tx, ctx, cancelled_clone);
+            decode(&roots, tx, ctx, &queue_clone, &cancelled_clone);
         });
-        Self { rx, cancelled }
+        Self { rx, queue, cancelled }
     }
 
     pub fn poll(&self) -> Poll {
-        match self.rx.try_recv() {
-            Ok(Ok(LoadResult { modified, name, image, dimensions })) => Poll::Image(modified, name, image, dimensions),
-            Ok(Err(e)) => Poll::Error(e),
-            Err(mpsc::TryRecvError::Empty) => Poll::Pending,
-            Err(mpsc::TryRecvError::Disconnected) => Poll::Done,
+        self.rx.try_recv().unwrap_or(Poll::Pending)
+    }
+
+    /// Decode the thumbnails of `paths` next, in this order, ahead of the
+    /// rest; also the ones decoded before and since dropped.
+    pub fn request(&self, paths: Vec<PathBuf>) {
+        let (lock, condvar) = &*self.queue;
+        let Ok(mut queue) = lock.lock() else { return };
+        for path in &paths {
+            queue.add(path.clone());
         }
+        queue.priority = paths.into_iter().rev().collect();
+        condvar.notify_all();
+    }
+
+    /// Whether thumbnails are still being decoded.
+    pub fn is_busy(&self) -> bool {
+        let Ok(queue) = self.queue.0.lock() else { return false };
+        !queue.remaining.is_empty() || !queue.in_flight.is_empty()
     }
 }
 
 fn decode(
-    path: &str,
-    tx: mpsc::SyncSender<LoadResultPayload>,
+    roots: &[PathBuf],
+    tx: mpsc::SyncSender<Poll>,
     ctx: egui::Context,
-    cancelled: Arc<AtomicBool>,
+    queue: &Shared,
+    cancelled: &AtomicBool,
 ) {
-    let entries = match std::fs::read_dir(path) {
-        Ok(e) => e,
-        Err(e) => {
-            let _ = tx.send(Err(format!("Error: {e}")));
-            ctx.request_repaint();
-            return;
+    // Roots may overlap, so every image is loaded once, for the first root it is found in.
+    let mut seen = HashSet::new();
+    let mut paths = Vec::new();
+    let mut errors = Vec::new();
+    for (root, dir) in roots.iter().enumerate() {
+        match scan(dir) {
+            Ok(found) => paths.extend(
+                found
+                    .into_iter()
+                    .filter(|p| seen.insert(p.clone()))
+                    .map(|p| (root, p)),
+            ),
+            Err(e) => errors.push(format!("{}: {e}", dir.display())),
         }
-    };
+    }
+    // One unreachable root (an unmounted disk) should not hide the others.
+    if paths.is_empty() && !errors.is_empty() {
+        let _ = tx.send(Poll::Error(format!("Error: {}", errors.join(", "))));
+        ctx.request_repaint();
+        return;
+    }
+
+    // Headers are quick to read, so the whole gallery is laid out before any decoding.
+    let found: Vec<PathBuf> = paths.par_iter().map_with(tx.clone(), |tx, &(root, ref path)| {
+        if cancelled.load(Ordering::Relaxed) { return None; }
+        let result = info(root, path);
+        let found = result.is_ok().then(|| path.clone());
+        let message = match result {
+            Ok(info) => Poll::Info(info),
+            Err(e) => Poll::Failed(path.clone(), e.to_string()),
+        };
+        tx.send(message).ok()?;
+        ctx.request_repaint();
+        found
+    }).flatten().collect();
+    let _ = tx.send(Poll::Scanned);
+    ctx.request_repaint();
+
+    let (lock, condvar) = queue;
+    if let Ok(mut queue) = lock.lock() {
+        for path in found.into_iter().rev() {
+            queue.add(path);
+        }
+    }
+    condvar.notify_all();
+}
+
+/// Work through the thumbnail queue, waiting for more until the loader is dropped.
+fn decode_thumbnails(
+    tx: &mpsc::SyncSender<Poll>,
+    ctx: &egui::Context,
+    queue: &Shared,
+    cancelled: &AtomicBool,
+) {
+    let (lock, condvar) = queue;
+    loop {
+        let Ok(mut guard) = lock.lock() else { return };
+        let (path, requested) = loop {
+            if cancelled.load(Ordering::Relaxed) { return; }
+            match guard.next() {
+                Some(next) => break next,
+                None => match condvar.wait(guard) {
+                    Ok(g) => guard = g,
+                    Err(_) => return,
+                },
+            }
+        };
+        drop(guard);
 
-    let paths: Vec<PathBuf> = entries
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.is_file()
-                && p.extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
-                    .unwrap_or(false)
-        })
-        .collect();
-
-    paths.par_iter().for_each_with(tx, |tx, path| {
-        if cancelled.load(Ordering::Relaxed) { return; }
-        let modified = path.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
-        let Ok((image, dimensions)) = load_image(path) else { return };
-        let name = path.to_string_lossy().into_owned();
-        if tx.send(Ok(LoadResult { modified, name, image, dimensions })).is_ok() {
-            ctx.request_repaint();
+        let result = thumbnail(&path);
+        if let Ok(mut queue) = lock.lock() {
+            queue.in_flight.remove(&path);
+            if result.is_err() {
+                queue.failed.insert(path.clone());
+            }
         }
-    });
+        let message = match result {
+            Ok(image) => Poll::Thumbnail { path, image, requested },
+            Err(e) => Poll::Failed(path, e.to_string()),
+        };
+        if tx.send(message).is_err() { return; }
+        ctx.request_repaint();
+    }
+}
+
+/// List image files inside `dir` and its subfolders, down to `MAX_DEPTH`
+/// levels. Hidden folders are skipped, and a folder reached again through a
+/// symlink is only read once.
+pub fn scan(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut visited = HashSet::from([dir.canonicalize()?]);
+    let mut paths = Vec::new();
+    scan_into(dir, 0, &mut visited, &mut paths)?;
+    Ok(paths)
+}
+
+fn scan_into(
+    dir: &Path,
+    depth: usize,
+    visited: &mut HashSet<PathBuf>,
+    paths: &mut Vec<PathBuf>,
+) -> Result<(), std::io::Error> {
+    for entry in std::fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if hidden || depth >= MAX_DEPTH {
+                continue;
+            }
+            if let Ok(real) = path.canonicalize()
+                && visited.insert(real)
+            {
+                // An unreadable subfolder should not hide the rest of the library.
+                let _ = scan_into(&path, depth + 1, visited, paths);
+            }
+        } else if path.is_file() && is_image(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(())
 }
 
-fn load_image(path: &Path) -> Result<(egui::ColorImage, [u32; 2]), image::ImageError> {
-    let (w, h) = image::image_dimensions(path)?;
-    let color_image = if let Some(cached) = cache::load(path) {
-        cached
+/// Whether `path` lies below `root` where `scan` would find it: not inside a
+/// hidden folder and at most `MAX_DEPTH` folders down.
+pub fn in_library(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    let folders: Vec<_> = relative.parent().into_iter().flatten().collect();
+    folders.len() <= MAX_DEPTH && !folders.iter().any(|f| f.to_string_lossy().starts_with('.'))
+}
+
+/// Whether the file extension is one of the supported image formats.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Read the metadata and pixel size of `path`, found below the root with index `root`.
+pub fn info(root: usize, path: &Path) -> Result<ImageInfo, image::ImageError> {
+    let metadata = path.metadata().ok();
+    let modified = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let (w, h) = dimensions(path)?;
+    Ok(ImageInfo {
+        root,
+        path: path.to_path_buf(),
+        modified,
+        file_size: metadata.map_or(0, |m| m.len()),
+        dimensions: [w, h],
+    })
+}
+
+/// Decode the image at `path`, telling the format from the content, so a
+/// misnamed file still opens, and turn it upright as its EXIF orientation says.
+/// Animated GIF and WebP give their first frame; SVGs are rendered at their
+/// intrinsic size. AVIF decodes only with the `avif` feature.
+pub fn open(path: &Path) -> Result<image::DynamicImage, image::ImageError> {
+    check_avif(path)?;
+    if svg::is_svg(path) {
+        return svg::render(&svg::load(path)?, 1.0).map(image::DynamicImage::from);
+    }
+    let mut decoder = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = orientation(&mut decoder);
+    let mut img = image::DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+/// Pixel size of the image at `path` once upright, read from its header.
+pub fn dimensions(path: &Path) -> Result<(u32, u32), image::ImageError> {
+    check_avif(path)?;
+    if svg::is_svg(path) {
+        return Ok(svg::size(&svg::load(path)?));
+    }
+    let mut decoder = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()?;
+    let (w, h) = decoder.dimensions();
+    Ok(match orientation(&mut decoder) {
+        Orientation::Rotate90
+        | Orientation::Rotate270
+        | Orientation::Rotate90FlipH
+        | Orientation::Rotate270FlipH => (h, w),
+        _ => (w, h),
+    })
+}
+
+/// Fail on `.avif` files in builds without a decoder for them, saying how to
+/// get one rather than that the format is unknown.
+fn check_avif(path: &Path) -> Result<(), image::ImageError> {
+    let avif = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("avif"));
+    if avif && !cfg!(feature = "avif") {
+        return Err(image::ImageError::IoError(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "AVIF support not built in (enable the `avif` feature)",
+        )));
+    }
+    Ok(())
+}
+
+/// The EXIF orientation of the image; a missing or unreadable tag leaves it as stored.
+fn orientation(decoder: &mut impl ImageDecoder) -> Orientation {
+    decoder.orientation().unwrap_or(Orientation::NoTransforms)
+}
+
+/// The gallery thumbnail of `path`, from the disk cache when it is fresh.
+pub fn thumbnail(path: &Path) -> Result<egui::ColorImage, image::ImageError> {
+    if let Some(cached) = cache::load(path) {
+        return Ok(cached);
+    }
+    let thumbnail = if svg::is_svg(path) {
+        // Vectors are rendered at the thumbnail size rather than scaled down.
+        let tree = svg::load(path)?;
+        let (w, h) = svg::size(&tree);
+        let scale = MAX_TEXTURE_SIZE as f32 / w.max(h) as f32;
+        image::DynamicImage::from(svg::render(&tree, scale)?)
     } else {
-        let img = image::open(path)?;
-        let thumbnail = img.thumbnail(MAX_TEXTURE_SIZE, MAX_TEXTURE_SIZE);
-        cache::save(path, &thumbnail);
-        cache::to_color_image(&thumbnail)
+        open_reduced(path)?.thumbnail(MAX_TEXTURE_SIZE, MAX_TEXTURE_SIZE)
+    };
+    cache::save(path, &thumbnail);
+    Ok(cache::to_color_image(&thumbnail))
+}
+
+/// Like `open`, but a JPEG is decoded only as large as a thumbnail needs.
+fn open_reduced(path: &Path) -> Result<image::DynamicImage, image::ImageError> {
+    let format = image::ImageReader::open(path)?
+        .with_guessed_format()?
+        .format();
+    if format == Some(image::ImageFormat::Jpeg)
+        && let Some(img) = open_jpeg_scaled(path, MAX_TEXTURE_SIZE as u16)
+    {
+        return Ok(img);
+    }
+    open(path)
+}
+
+/// Decode the JPEG at `path` at 1/2, 1/4 or 1/8 scale in the DCT, as small as
+/// it goes while the longer side stays at least `size`, and turn it upright.
+/// `None` when this decoder cannot handle the file, so `open` decodes it in full.
+fn open_jpeg_scaled(path: &Path, size: u16) -> Option<image::DynamicImage> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut decoder = jpeg_decoder::Decoder::new(std::io::BufReader::new(file));
+    let (w, h) = decoder.scale(size, size).ok()?;
+    let pixels = decoder.decode().ok()?;
+    let (w, h) = (u32::from(w), u32::from(h));
+    let mut img: image::DynamicImage = match decoder.info()?.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => image::GrayImage::from_raw(w, h, pixels)?.into(),
+        jpeg_decoder::PixelFormat::RGB24 => image::RgbImage::from_raw(w, h, pixels)?.into(),
+        // 16-bit lossless and CMYK JPEGs are rare; leave them to `open`.
+        _ => return None,
     };
-    Ok((color_image, [w, h]))
+    let orientation = decoder.exif_data().and_then(Orientation::from_exif_chunk);
+    img.apply_orientation(orientation.unwrap_or(Orientation::NoTransforms));
+    Some(img)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_takes_priority_paths_first_and_once() {
+        let mut queue = Queue::default();
+        for path in ["d", "c", "b", "a"] {
+            queue.add(PathBuf::from(path));
+        }
+        assert_eq!(queue.next(), Some((PathBuf::from("a"), false)));
+        // Scrolled to c; a is still being decoded and is not queued again.
+        queue.add(PathBuf::from("a"));
+        queue.priority = ["d", "c"].map(PathBuf::from).into();
+        assert_eq!(queue.next(), Some((PathBuf::from("c"), true)));
+        assert_eq!(queue.next(), Some((PathBuf::from("d"), true)));
+        assert_eq!(queue.next(), Some((PathBuf::from("b"), false)));
+        assert_eq!(queue.next(), None);
+
+        // Dropped thumbnails can be asked for again.
+        queue.in_flight.clear();
+        queue.add(PathBuf::from("c"));
+        assert_eq!(queue.next(), Some((PathBuf::from("c"), false)));
+    }
+
+    #[test]
+    fn scan_recurses_skipping_hidden_and_loops() {
+        let root = std::env::temp_dir().join(format!("nitrohydra-scan-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["art/winter", ".thumbnails"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "a.jpg",
+            "art/b.png",
+            "art/winter/c.JPG",
+            ".thumbnails/d.jpg",
+            "notes.txt",
+        ] {
+            std::fs::write(root.join(file), b"").unwrap();
+        }
+        std::os::unix::fs::symlink(&root, root.join("art/loop")).unwrap();
+
+        let mut found: Vec<PathBuf> = scan(&root)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        found.sort();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            found,
+            ["a.jpg", "art/b.png", "art/winter/c.JPG"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn open_reads_the_format_from_the_content() {
+        let dir = std::env::temp_dir().join(format!("nitrohydra-open-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = image::RgbaImage::new(6, 4);
+        let formats = [
+            ("a.jpg", image::ImageFormat::Png),
+            ("b.gif", image::ImageFormat::Gif),
+            ("c.webp", image::ImageFormat::WebP),
+            ("d.bmp", image::ImageFormat::Bmp),
+            ("e.png", image::ImageFormat::Qoi),
+            ("f.tiff", image::ImageFormat::Tiff),
+        ];
+        for (name, format) in formats {
+            image.save_with_format(dir.join(name), format).unwrap();
+        }
+
+        for (name, _) in formats {
+            let path = dir.join(name);
+            assert_eq!(dimensions(&path).unwrap(), (6, 4), "{name}");
+            assert_eq!(open(&path).unwrap().width(), 6, "{name}");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_applies_exif_orientation() {
+        use image::ImageEncoder;
+
+        let dir = std::env::temp_dir().join(format!("nitrohydra-exif-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("portrait.jpg");
+        // Little-endian TIFF header and one IFD entry: Orientation (0x0112) = 6,
+        // i.e. rotate 90° clockwise to display.
+        let exif = [
+            b"II*\0".as_slice(),
+            &8u32.to_le_bytes(),
+            &1u16.to_le_bytes(),
+            &[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0],
+            &0u32.to_le_bytes(),
+        ]
+        .concat();
+        let mut encoder =
+            image::codecs::jpeg::JpegEncoder::new(std::fs::File::create(&path).unwrap());
+        encoder.set_exif_metadata(exif).unwrap();
+        let image = image::RgbImage::new(6, 4);
+        encoder
+            .write_image(&image, 6, 4, image::ExtendedColorType::Rgb8)
+            .unwrap();
+
+        assert_eq!(dimensions(&path).unwrap(), (4, 6));
+        for img in [open(&path).unwrap(), open_reduced(&path).unwrap()] {
+            assert_eq!((img.width(), img.height()), (4, 6));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn open_reduced_decodes_large_jpegs_at_a_fraction() {
+        let dir = std::env::temp_dir().join(format!("nitrohydra-reduced-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("large.jpg");
+        image::RgbImage::new(2400, 1200).save(&path).unwrap();
+
+        // 1/4 would be 600×300; 1/8 would leave the longer side under 512.
+        let img = open_reduced(&path).unwrap();
+        assert_eq!((img.width(), img.height()), (600, 300));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn in_library_matches_scan_rules() {
+        let root = Path::new("/w");
+        assert!(in_library(root, Path::new("/w/art/a.jpg")));
+        assert!(!in_library(root, Path::new("/w/.cache/a.jpg")));
+        assert!(!in_library(root, Path::new("/other/a.jpg")));
+        assert!(!in_library(root, Path::new("/w/1/2/3/4/5/6/7/8/9/a.jpg")));
+        assert!(is_image(Path::new("/w/a.AVIF")));
+    }
+
+    #[test]
+    #[cfg(not(feature = "avif"))]
+    fn avif_fails_with_hint_without_feature() {
+        let e = dimensions(Path::new("/w/a.avif")).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "AVIF support not built in (enable the `avif` feature)"
+        );
+        assert!(open(Path::new("/w/a.AVIF")).is_err());
+    }
 }
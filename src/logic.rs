@@ -1,103 +1,347 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use eframe::egui;
+use rayon::prelude::*;
 
 use crate::apply_job::ApplyJob;
-use crate::gallery::Gallery;
+use crate::export;
+use crate::filter::Filter;
+use crate::gallery::{Gallery, ImageEntry, Root};
+use crate::history;
+use crate::layouts;
+use crate::loader;
 use crate::monitors::{self, Monitor};
+use crate::overrides;
 use crate::preview::PreviewJob;
+use crate::profiles::{self, Profile};
+use crate::random::{self, Candidate};
+use crate::schedule::{self, Schedule};
 use crate::selection::Selection;
-use crate::wallpaper;
+use crate::slideshow::{self, Source};
+use crate::wallpaper::{self, Assignment, Framing};
+use crate::watch;
 
 pub(crate) struct App {
-    pub(crate) path: String,
+    pub(crate) roots: Vec<Root>,
     pub(crate) gallery: Gallery,
+    /// Subfolder the gallery is narrowed to; `None` shows everything.
+    pub(crate) folder: Option<PathBuf>,
+    pub(crate) filter: Filter,
     pub(crate) thumb_size: f32,
-    pub(crate) selected: Selection,
+    pub(crate) selected: Selection<PathBuf>,
     pub(crate) monitors: Result<Vec<Monitor>, String>,
+    /// Whether the monitor overrides file exists and shaped `monitors`.
+    pub(crate) monitor_overrides: bool,
     pub(crate) apply: ApplyJob,
     pub(crate) preview: PreviewJob,
-    pub(crate) preview_items: Option<[usize; 2]>,
+    pub(crate) preview_key: Option<([PathBuf; 2], [Framing; 2])>,
+    pub(crate) framing: [Framing; 2],
+    pub(crate) profiles: Vec<Profile>,
+    pub(crate) profile_name: String,
+    pub(crate) renaming: Option<(usize, String)>,
+    pub(crate) profiles_error: Option<String>,
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
-            path: String::new(),
+            roots: Vec::new(),
             gallery: Gallery::new(),
+            folder: None,
+            filter: Filter::default(),
             thumb_size: 150.0,
             selected: Selection::new(),
             monitors: Ok(Vec::new()),
+            monitor_overrides: false,
             apply: ApplyJob::new(),
             preview: PreviewJob::new(),
-            preview_items: None,
+            preview_key: None,
+            framing: [Framing::default(); 2],
+            profiles: Vec::new(),
+            profile_name: String::new(),
+            renaming: None,
+            profiles_error: None,
         }
     }
 }
 
 impl App {
     pub(crate) fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let path: String = cc
+        let roots: Vec<Root> = cc
             .storage
-            .and_then(|s| eframe::get_value(s, "path"))
+            .and_then(|s| {
+                eframe::get_value(s, "roots").or_else(|| {
+                    // Earlier versions kept a single directory.
+                    let path: String = eframe::get_value(s, "path")?;
+                    Some(vec![Root {
+                        path,
+                        enabled: true,
+                    }])
+                })
+            })
+            .unwrap_or_default();
+        let sort = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, "sort"))
             .unwrap_or_default();
 
         let mut style = (*cc.egui_ctx.style()).clone();
         style.spacing.button_padding += egui::vec2(3.0, 3.0);
         cc.egui_ctx.set_style(style);
 
+        let (profiles, profiles_error) = match profiles::load() {
+            Ok(p) => (p, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+
         let mut app = Self {
-            path: path.clone(),
+            roots,
             monitors: monitors::detect(),
+            monitor_overrides: overrides::path().exists(),
+            profiles,
+            profiles_error,
             ..Self::default()
         };
-        app.gallery.load(&path, &cc.egui_ctx);
+        app.gallery.set_sort_order(sort);
+        if let Ok(monitors) = &app.monitors {
+            app.gallery.set_monitors(monitors.clone());
+        }
+        app.load_images(&cc.egui_ctx);
         app
     }
 
     pub(crate) fn load_images(&mut self, ctx: &egui::Context) {
-        self.gallery.load(&self.path, ctx);
-        self.selected.clear();
+        // Canonical roots give every image the same path whichever way its folder was typed in.
+        let roots: Vec<PathBuf> = self
+            .roots
+            .iter()
+            .filter(|r| r.enabled && !r.path.is_empty())
+            .map(|r| PathBuf::from(&r.path))
+            .map(|p| p.canonicalize().unwrap_or(p))
+            .collect();
+        self.gallery.load(&roots, ctx);
+        self.folder = None;
+    }
+
+    /// Take in gallery changes. Once the scan is done, images that are gone
+    /// drop out of the selection together with their framing.
+    pub(crate) fn poll_gallery(&mut self, ctx: &egui::Context) {
+        if !self.gallery.poll(ctx) || self.gallery.is_scanning() || self.selected.is_empty() {
+            return;
+        }
+        let before = self.selected.items().to_vec();
+        self.selected.retain(|p| self.gallery.find(p).is_some());
+        if self.selected.len() == before.len() {
+            return;
+        }
+        let kept: Vec<Framing> = before
+            .iter()
+            .zip(self.framing)
+            .filter(|(p, _)| self.selected.items().contains(p))
+            .map(|(_, f)| f)
+            .collect();
+        self.framing = [Framing::default(); 2];
+        self.framing[..kept.len()].copy_from_slice(&kept);
+    }
+
+    /// Whether the folder tree has anything to choose from.
+    pub(crate) fn has_folders(&self) -> bool {
+        let folders = self.gallery.folders();
+        folders.len() > 1 || folders.iter().any(|f| !f.children.is_empty())
+    }
+
+    /// Whether the gallery lists any images, so there is something to shuffle.
+    pub(crate) fn has_images(&self) -> bool {
+        self.gallery.entries().is_some_and(|e| !e.is_empty())
+    }
+
+    /// Whether `entry` lies inside the folder the gallery is narrowed to.
+    pub(crate) fn in_folder(&self, entry: &ImageEntry) -> bool {
+        self.folder
+            .as_ref()
+            .is_none_or(|folder| entry.path.starts_with(folder))
+    }
+
+    /// Whether `entry` is in the chosen folder and passes the filter bar.
+    pub(crate) fn is_visible(&self, entry: &ImageEntry) -> bool {
+        let monitors = self.monitors.as_deref().unwrap_or_default();
+        self.in_folder(entry)
+            && self
+                .filter
+                .matches(&entry.path, entry.original_size, monitors)
+    }
+
+    /// Selected images placed on the monitors, once both slots are filled.
+    pub(crate) fn assignments(&self) -> Option<Vec<Assignment>> {
+        let monitors = self.monitors.as_ref().ok()?;
+        if self.selected.len() != 2 || monitors.len() < 2 {
+            return None;
+        }
+        let assignments = self
+            .selected
+            .items()
+            .iter()
+            .zip(monitors.iter())
+            .zip(self.framing)
+            .map(|((path, monitor), framing)| Assignment {
+                path: path.clone(),
+                monitor: monitor.clone(),
+                framing,
+            })
+            .collect();
+        Some(assignments)
     }
 
     pub(crate) fn auto_preview(&mut self, ctx: &egui::Context) {
-        if self.selected.len() != 2 || !matches!(&self.monitors, Ok(m) if m.len() >= 2) {
-            if self.preview_items.is_some() {
+        let Some(assignments) = self.assignments() else {
+            if self.preview_key.is_some() {
                 self.preview.clear();
-                self.preview_items = None;
+                self.preview_key = None;
             }
             return;
-        }
+        };
 
-        let items = [self.selected.items()[0], self.selected.items()[1]];
-        if self.preview_items == Some(items) || self.preview.is_running() {
+        let items = [0, 1].map(|slot| self.selected.items()[slot].clone());
+        let key = (items, self.framing);
+        if self.preview_key.as_ref() == Some(&key) || self.preview.is_running() {
             return;
         }
 
-        let Some(entries) = self.gallery.entries() else {
+        self.preview.start(assignments, ctx);
+        self.preview_key = Some(key);
+    }
+
+    /// Select random images from the gallery for the detected monitors.
+    pub(crate) fn shuffle(&mut self) {
+        let (Some(entries), Ok(monitors)) = (self.gallery.entries(), &self.monitors) else {
             return;
         };
-        let monitors = self.monitors.as_ref().unwrap();
-        let assignments: Vec<(PathBuf, Monitor)> = self
-            .selected
-            .items()
+        let candidates: Vec<Candidate> = entries
             .iter()
-            .zip(monitors.iter())
-            .map(|(&idx, monitor)| {
-                let path = PathBuf::from(entries[idx].texture.name());
-                (path, monitor.clone())
+            .filter(|e| self.is_visible(e))
+            .map(|e| Candidate {
+                path: &e.path,
+                size: Some(e.original_size),
             })
             .collect();
-        self.preview.start(assignments, ctx);
-        self.preview_items = Some(items);
+        let slots = &monitors[..monitors.len().min(2)];
+        let picked = random::pick(
+            &candidates,
+            slots,
+            &history::recent(),
+            true,
+            &mut rand::rng(),
+        );
+        if !picked.is_empty() {
+            let picked = picked
+                .into_iter()
+                .map(|i| candidates[i].path.to_path_buf())
+                .collect();
+            self.select(|s| s.set(picked));
+        }
     }
 
-    pub(crate) fn handle_image_click(&mut self, index: usize, shift: bool) {
+    pub(crate) fn handle_image_click(&mut self, path: PathBuf, shift: bool) {
+        self.select(|s| s.click(path, shift));
+    }
+
+    /// Change the selection; framing follows swapped images and resets for replaced ones.
+    fn select(&mut self, change: impl FnOnce(&mut Selection<PathBuf>)) {
         self.apply.clear_status();
-        self.selected.click(index, shift);
+        let before = self.selected.items().to_vec();
+        change(&mut self.selected);
+        let after = self.selected.items();
+
+        if before.len() == 2 && after.len() == 2 && before[0] == after[1] && before[1] == after[0] {
+            self.framing.swap(0, 1);
+            return;
+        }
+        for (slot, framing) in self.framing.iter_mut().enumerate() {
+            if before.get(slot) != after.get(slot) {
+                framing.crop = Framing::default().crop;
+            }
+        }
+    }
+
+    pub(crate) fn save_profile(&mut self) {
+        let name = self.profile_name.trim().to_string();
+        let Some(assignments) = self.assignments() else {
+            return;
+        };
+        if name.is_empty() {
+            return;
+        }
+
+        let profile = Profile::from_assignments(name, &assignments);
+        match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+        self.profile_name.clear();
+        self.store_profiles();
+    }
+
+    pub(crate) fn rename_profile(&mut self, index: usize, name: String) {
+        let name = name.trim().to_string();
+        if name.is_empty() || self.profiles.iter().any(|p| p.name == name) {
+            return;
+        }
+        self.profiles[index].name = name;
+        self.store_profiles();
+    }
+
+    pub(crate) fn delete_profile(&mut self, index: usize) {
+        self.profiles.remove(index);
+        self.store_profiles();
+    }
+
+    pub(crate) fn apply_profile(&mut self, index: usize, ctx: &egui::Context) {
+        let Ok(monitors) = &self.monitors else {
+            return;
+        };
+        match self.profiles[index].assignments(monitors) {
+            Ok(assignments) => self.apply_saved(assignments, ctx),
+            Err(e) => self.profiles_error = Some(e),
+        }
+    }
+
+    /// Re-apply the images last used with the connected monitors.
+    pub(crate) fn restore_layout(&mut self, ctx: &egui::Context) {
+        let Ok(monitors) = &self.monitors else {
+            return;
+        };
+        match layouts::matching(monitors) {
+            Ok(Some(assignments)) => self.apply_saved(assignments, ctx),
+            Ok(None) => self.profiles_error = Some(NO_LAYOUT.into()),
+            Err(e) => self.profiles_error = Some(e),
+        }
+    }
+
+    /// Apply saved assignments, and select their images when they are in the gallery.
+    fn apply_saved(&mut self, assignments: Vec<Assignment>, ctx: &egui::Context) {
+        let in_gallery = assignments
+            .iter()
+            .all(|a| self.gallery.find(&a.path).is_some());
+        if in_gallery && assignments.len() == 2 {
+            let paths = assignments.iter().map(|a| a.path.clone()).collect();
+            self.select(|s| s.set(paths));
+            for (slot, a) in assignments.iter().enumerate() {
+                self.framing[slot] = a.framing;
+            }
+        }
+
+        self.profiles_error = None;
+        self.apply.start(assignments, ctx);
+    }
+
+    fn store_profiles(&mut self) {
+        self.profiles_error = profiles::save(&self.profiles).err();
     }
 }
 
+const NO_LAYOUT: &str = "no wallpaper remembered for this monitor setup";
+
 pub(crate) fn show_help() {
     let bin = std::env::args()
         .next()
@@ -107,33 +351,303 @@ pub(crate) fn show_help() {
     eprintln!("Usage:");
     eprintln!("  {bin}                     Start the GUI");
     eprintln!("  {bin} <image1> <image2>   Join images and set as wallpaper");
+    eprintln!("  {bin} random <dir> [--fit]");
+    eprintln!("                            Pick a random image per monitor and apply");
+    eprintln!("  {bin} slideshow <dir|playlist> [--interval <minutes>] [--fit] [--unit]");
+    eprintln!("  {bin} slideshow --favorites [--interval <minutes>] [--fit] [--unit]");
+    eprintln!("                            Re-pick and apply images every 30 minutes");
+    eprintln!("                            (--unit prints a systemd user unit instead)");
+    eprintln!("  {bin} schedule [<file>] [--fit] [--dry-run [--hours <n>]]");
+    eprintln!("                            Apply images following a schedule file");
+    eprintln!("                            (default ~/.config/nitrohydra/schedule)");
+    eprintln!(
+        "                            (--dry-run looks up to {} hours ahead)",
+        schedule::MAX_DRY_RUN_HOURS
+    );
+    eprintln!("  {bin} export <dir|playlist|--favorites> [--count <n>] [--duration <minutes>]");
+    eprintln!("         [--fit]");
+    eprintln!("                            Compose a GNOME/Cinnamon XML slideshow and set it");
+    eprintln!("  {bin} profile list        List saved profiles");
+    eprintln!("  {bin} profile apply <name>");
+    eprintln!("                            Apply a profile saved in the GUI");
+    eprintln!("  {bin} restore             Re-apply the images last used with the");
+    eprintln!("                            connected monitor setup");
+    eprintln!("  {bin} watch               Recompose the wallpaper when monitors are");
+    eprintln!("                            plugged or unplugged");
+    eprintln!("  {bin} scan <dir>...       List images in the folders that fail to load");
     eprintln!();
     eprintln!("Images are assigned to monitors left-to-right.");
+    eprintln!("With --fit, images matching each monitor's aspect ratio are preferred.");
 }
 
 pub(crate) fn run_cli(left: &str, right: &str) {
-    let monitors = match monitors::detect() {
-        Ok(m) => m,
+    let monitors = detect_monitors();
+
+    if monitors.len() < 2 {
+        eprintln!("error: need at least 2 monitors, found {}", monitors.len());
+        std::process::exit(1);
+    }
+
+    let assignments = vec![
+        Assignment::new(PathBuf::from(left), monitors[0].clone()),
+        Assignment::new(PathBuf::from(right), monitors[1].clone()),
+    ];
+
+    apply_or_exit(&assignments);
+}
+
+pub(crate) fn run_random(args: &[String]) {
+    let mut dir = None;
+    let mut prefer_fit = false;
+    for arg in args {
+        match arg.as_str() {
+            "--fit" => prefer_fit = true,
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => exit_with_help(),
+        }
+    }
+    let Some(dir) = dir else { exit_with_help() };
+
+    let monitors = detect_monitors();
+    let assignments = random::pick_from_dir(&dir, &monitors, prefer_fit).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    });
+    for a in &assignments {
+        eprintln!("{}: {}", a.monitor.name, a.path.display());
+    }
+
+    apply_or_exit(&assignments);
+}
+
+pub(crate) fn run_slideshow(args: &[String]) {
+    let mut source = None;
+    let mut interval = 30;
+    let mut prefer_fit = false;
+    let mut unit = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--favorites" if source.is_none() => source = Some(Source::Favorites),
+            "--interval" => {
+                interval = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(minutes) if minutes > 0 => minutes,
+                    _ => exit_with_help(),
+                }
+            }
+            "--fit" => prefer_fit = true,
+            "--unit" => unit = true,
+            _ if source.is_none() => source = Some(Source::from_path(Path::new(arg))),
+            _ => exit_with_help(),
+        }
+    }
+    let Some(source) = source else {
+        exit_with_help()
+    };
+
+    let options = slideshow::Options {
+        source,
+        interval: Duration::from_secs(interval * 60),
+        prefer_fit,
+    };
+    let result = if unit {
+        slideshow::systemd_unit(&options).map(|unit| print!("{unit}"))
+    } else {
+        slideshow::run(&options)
+    };
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+pub(crate) fn run_schedule(args: &[String]) {
+    let mut file = None;
+    let mut dry_run = false;
+    let mut hours = 48;
+    let mut prefer_fit = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--hours" => {
+                hours = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(h) if (1..=schedule::MAX_DRY_RUN_HOURS).contains(&h) => h,
+                    _ => exit_with_help(),
+                }
+            }
+            "--fit" => prefer_fit = true,
+            _ if file.is_none() => file = Some(PathBuf::from(arg)),
+            _ => exit_with_help(),
+        }
+    }
+    let file = file.unwrap_or_else(schedule::default_path);
+
+    let result = Schedule::load(&file).and_then(|schedule| {
+        if dry_run {
+            schedule::dry_run(&schedule, hours);
+            Ok(())
+        } else {
+            schedule::run(&schedule, prefer_fit)
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+}
+
+pub(crate) fn run_export(args: &[String]) {
+    let mut source = None;
+    let mut count = 10;
+    let mut duration_minutes = 30;
+    let mut prefer_fit = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--favorites" if source.is_none() => source = Some(Source::Favorites),
+            "--count" => {
+                count = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(n) if n > 0 => n,
+                    _ => exit_with_help(),
+                }
+            }
+            "--duration" => {
+                duration_minutes = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(minutes) if minutes > 0 => minutes,
+                    _ => exit_with_help(),
+                }
+            }
+            "--fit" => prefer_fit = true,
+            _ if source.is_none() => source = Some(Source::from_path(Path::new(arg))),
+            _ => exit_with_help(),
+        }
+    }
+    let Some(source) = source else {
+        exit_with_help()
+    };
+
+    let options = export::Options {
+        source,
+        count,
+        duration_minutes,
+        prefer_fit,
+    };
+    match export::run(&options) {
+        Ok(xml) => eprintln!("Slideshow applied: {}", xml.display()),
         Err(e) => {
             eprintln!("error: {e}");
             std::process::exit(1);
         }
-    };
+    }
+}
 
-    if monitors.len() < 2 {
-        eprintln!("error: need at least 2 monitors, found {}", monitors.len());
+pub(crate) fn run_profile(args: &[String]) {
+    let profiles = profiles::load().unwrap_or_else(|e| {
+        eprintln!("error: {e}");
         std::process::exit(1);
+    });
+
+    match args {
+        [cmd] if cmd == "list" => {
+            for profile in &profiles {
+                println!("{}", profile.name);
+            }
+        }
+        [cmd, name] if cmd == "apply" => {
+            let Some(profile) = profiles::find(&profiles, name) else {
+                eprintln!("error: no profile named '{name}'");
+                std::process::exit(1);
+            };
+            let assignments = profile.assignments(&detect_monitors()).unwrap_or_else(|e| {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            });
+            apply_or_exit(&assignments);
+        }
+        _ => exit_with_help(),
     }
+}
 
-    let assignments: Vec<(PathBuf, Monitor)> = vec![
-        (PathBuf::from(left), monitors[0].clone()),
-        (PathBuf::from(right), monitors[1].clone()),
-    ];
+pub(crate) fn run_restore(args: &[String]) {
+    if !args.is_empty() {
+        exit_with_help();
+    }
+    match layouts::matching(&detect_monitors()) {
+        Ok(Some(assignments)) => apply_or_exit(&assignments),
+        Ok(None) => {
+            eprintln!("error: {NO_LAYOUT}");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        }
+    }
+}
 
-    if let Err(e) = wallpaper::apply(&assignments, &|msg| eprintln!("{msg}")) {
+pub(crate) fn run_watch(args: &[String]) {
+    if !args.is_empty() {
+        exit_with_help();
+    }
+    if let Err(e) = watch::run() {
         eprintln!("error: {e}");
         std::process::exit(1);
     }
+}
+
+pub(crate) fn run_scan(args: &[String]) {
+    if args.is_empty() {
+        exit_with_help();
+    }
+    let mut paths = Vec::new();
+    for dir in args {
+        match loader::scan(Path::new(dir)) {
+            Ok(found) => paths.extend(found),
+            Err(e) => {
+                eprintln!("error: failed to read {dir}: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Decoding fills the thumbnail cache as a side effect, like the gallery does.
+    let mut failures: Vec<(&PathBuf, String)> = paths
+        .par_iter()
+        .filter_map(|path| {
+            let result = loader::info(0, path).and_then(|_| loader::thumbnail(path));
+            result.err().map(|e| (path, e.to_string()))
+        })
+        .collect();
+    failures.sort();
+    for (path, error) in &failures {
+        println!("{}: {error}", path.display());
+    }
+    eprintln!("{} images, {} failed", paths.len(), failures.len());
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn detect_monitors() -> Vec<Monitor> {
+    monitors::detect().unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    })
+}
+
+fn apply_or_exit(assignments: &[Assignment]) {
+    if let Err(e) = wallpaper::apply(assignments, &|msg| eprintln!("{msg}")) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
+    // So `watch` can recompose it when the monitors change.
+    let _ = layouts::remember(assignments);
 
     eprintln!("Wallpaper applied!");
 }
+
+fn exit_with_help() -> ! {
+    show_help();
+    std::process::exit(1);
+}
@@ -1,22 +1,47 @@
 mod apply_job;
+mod backend;
 mod cache;
+mod config;
+mod export;
+mod filter;
+mod folders;
+mod fswatch;
 mod gallery;
+mod history;
+mod layouts;
 mod loader;
 mod logic;
 mod monitors;
+mod overrides;
 mod preview;
+mod profiles;
+mod random;
+mod schedule;
 mod selection;
+mod slideshow;
+mod sort;
+mod svg;
+mod textures;
 mod ui;
 mod wallpaper;
+mod watch;
 
 use eframe::egui;
 use logic::App;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
-    match args.len() {
-        1 => run_gui(),
-        3 => logic::run_cli(&args[1], &args[2]),
+    match args.as_slice() {
+        [_] => run_gui(),
+        [_, cmd, rest @ ..] if cmd == "random" => logic::run_random(rest),
+        [_, cmd, rest @ ..] if cmd == "slideshow" => logic::run_slideshow(rest),
+        [_, cmd, rest @ ..] if cmd == "schedule" => logic::run_schedule(rest),
+        [_, cmd, rest @ ..] if cmd == "export" => logic::run_export(rest),
+        [_, cmd, rest @ ..] if cmd == "profile" => logic::run_profile(rest),
+        [_, cmd, rest @ ..] if cmd == "restore" => logic::run_restore(rest),
+        [_, cmd, rest @ ..] if cmd == "watch" => logic::run_watch(rest),
+        [_, cmd, rest @ ..] if cmd == "scan" => logic::run_scan(rest),
+        [_, left, right] => logic::run_cli(left, right),
         _ => {
             logic::show_help();
             let is_help = args.get(1).is_some_and(|a| a == "--help" || a == "-h");
@@ -42,11 +67,12 @@ fn run_gui() {
 
 impl eframe::App for App {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, "path", &self.path);
+        eframe::set_value(storage, "roots", &self.roots);
+        eframe::set_value(storage, "sort", &self.gallery.sort_order());
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.gallery.poll(ctx);
+        self.poll_gallery(ctx);
         self.apply.poll();
         self.preview.poll(ctx);
         self.auto_preview(ctx);
@@ -57,12 +83,19 @@ impl eframe::App for App {
                     .inner_margin(egui::Margin::symmetric(8.0, 12.0)),
             )
             .resizable(false)
-            .show_animated(ctx, !self.selected.is_empty(), |ui| {
+            .show_animated(ctx, !self.selected.is_empty() || self.has_images(), |ui| {
                 self.show_selection(ui);
             });
 
+        egui::SidePanel::left("folders_panel")
+            .resizable(true)
+            .show_animated(ctx, self.has_folders(), |ui| {
+                self.show_folders(ui);
+            });
+
         egui::CentralPanel::default().show(ctx, |ui| {
-            self.show_path_input(ui);
+            self.show_roots(ui);
+            self.show_profiles(ui);
             self.show_size_slider(ui);
             ui.separator();
             self.show_gallery(ui);
@@ -1,31 +1,214 @@
-use std::process::Command;
+use serde::Deserialize;
+
+use crate::backend::{self, Backend};
+use crate::overrides;
 
 #[derive(Debug, Clone)]
 pub struct Monitor {
+    /// Shown to the user; an overrides `rename` replaces it.
     pub name: String,
+    /// Output name the system reports, e.g. `DP-1`; overrides never change it.
+    pub connector: String,
     pub width: u32,
     pub height: u32,
     pub x: u32,
     pub y: u32,
+    /// Physical pixels per logical pixel; the geometry above is logical.
+    pub scale: f64,
+    /// Identity read from the display's EDID, when the output reports one.
+    pub edid: Option<Edid>,
+    /// Other outputs cloning this one's pixels, merged into the same slot.
+    pub mirrors: Vec<String>,
+}
+
+/// Manufacturer, model and serial number decoded from an EDID block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edid {
+    /// Three-letter PNP vendor ID, e.g. `DEL`.
+    pub manufacturer: String,
+    /// Monitor name descriptor, or the product code when there is none.
+    pub model: String,
+    /// Serial number descriptor or numeric serial; empty if neither is set.
+    pub serial: String,
+}
+
+impl Monitor {
+    /// A name for this display that survives connector changes: the EDID
+    /// identity when known, otherwise the connector name.
+    pub fn id(&self) -> String {
+        match &self.edid {
+            Some(edid) => edid.id(),
+            None => self.connector.clone(),
+        }
+    }
+
+    /// The size of the output in device pixels.
+    pub fn physical_size(&self) -> (u32, u32) {
+        let scale = |v: u32| (f64::from(v) * self.scale).round() as u32;
+        (scale(self.width), scale(self.height))
+    }
+}
+
+#[cfg(test)]
+impl Monitor {
+    /// A `width`×`height` output at (`x`, 0), unscaled, without EDID or mirrors.
+    pub fn test(name: &str, width: u32, height: u32, x: u32) -> Self {
+        Self {
+            name: name.into(),
+            connector: name.into(),
+            width,
+            height,
+            x,
+            y: 0,
+            scale: 1.0,
+            edid: None,
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+impl Edid {
+    pub fn id(&self) -> String {
+        format!("{}:{}:{}", self.manufacturer, self.model, self.serial)
+    }
+
+    /// Decode the vendor block of a raw EDID.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+        if bytes.len() < 128 || bytes[..8] != HEADER {
+            return None;
+        }
+
+        // Three 5-bit letters, 'A' = 1.
+        let vendor = u16::from_be_bytes([bytes[8], bytes[9]]);
+        let manufacturer: String = [10, 5, 0]
+            .iter()
+            .map(|shift| (b'A' - 1 + ((vendor >> shift) & 0x1f) as u8) as char)
+            .collect();
+        let product = u16::from_le_bytes([bytes[10], bytes[11]]);
+        let serial_number = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]);
+
+        let mut name = None;
+        let mut serial = None;
+        for descriptor in bytes[54..126].chunks(18) {
+            if descriptor[..3] != [0, 0, 0] {
+                continue; // a detailed timing, not a text descriptor
+            }
+            let text = descriptor_text(&descriptor[5..]);
+            match descriptor[3] {
+                0xfc => name = Some(text),
+                0xff => serial = Some(text),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            manufacturer,
+            model: name
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| format!("{product:04x}")),
+            serial: serial
+                .filter(|s| !s.is_empty())
+                .or_else(|| (serial_number != 0).then(|| serial_number.to_string()))
+                .unwrap_or_default(),
+        })
+    }
 }
 
-/// Detect connected monitors by parsing `xrandr --query`.
-/// Returns monitors sorted left-to-right (by x offset).
+/// Descriptor text ends at a newline and is padded with spaces.
+fn descriptor_text(bytes: &[u8]) -> String {
+    let end = bytes
+        .iter()
+        .position(|&b| b == b'\n')
+        .unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+/// Detect connected monitors with the overrides file applied: from the
+/// compositor on sway and Hyprland, otherwise by parsing `xrandr --query --props`.
+/// Returns monitors in slot order: left-to-right (by x offset) unless the
+/// overrides reorder them.
 pub fn detect() -> Result<Vec<Monitor>, String> {
-    let output = Command::new("xrandr")
-        .arg("--query")
-        .output()
-        .map_err(|e| format!("failed to run xrandr: {e}"))?;
+    let mut monitors = match Backend::detect() {
+        Backend::Gsettings => parse(&backend::run("xrandr", &["--query", "--props"])?),
+        Backend::Sway => parse_sway(&backend::run("swaymsg", &["-t", "get_outputs", "--raw"])?)?,
+        Backend::Hyprpaper => parse_hyprland(&backend::run("hyprctl", &["monitors", "-j"])?)?,
+    };
+    if let Some(overrides) = overrides::load()? {
+        monitors = overrides.apply(monitors);
+    }
+    let monitors = merge_mirrors(monitors);
+    if monitors.is_empty() {
+        return Err("no connected monitors found".into());
+    }
+    Ok(monitors)
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("xrandr failed: {stderr}"));
+/// Fold outputs with identical geometry into the first of them: mirrored
+/// screens share their pixels, so they take a single image.
+fn merge_mirrors(monitors: Vec<Monitor>) -> Vec<Monitor> {
+    let mut merged: Vec<Monitor> = Vec::new();
+    for monitor in monitors {
+        let geometry = |m: &Monitor| (m.width, m.height, m.x, m.y);
+        match merged
+            .iter_mut()
+            .find(|m| geometry(m) == geometry(&monitor))
+        {
+            Some(original) => {
+                original.mirrors.push(monitor.connector);
+                original.mirrors.extend(monitor.mirrors);
+            }
+            None => merged.push(monitor),
+        }
     }
+    merged
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut monitors = Vec::new();
+fn parse(stdout: &str) -> Vec<Monitor> {
+    let mut monitors: Vec<Monitor> = Vec::new();
+    // Hex of the EDID property being read, and whether it belongs to a monitor.
+    let mut edid: Option<String> = None;
+    let mut in_output = false;
 
     for line in stdout.lines() {
+        if let Some(hex) = edid.as_mut() {
+            let chunk = line.trim();
+            if !chunk.is_empty() && chunk.chars().all(|c| c.is_ascii_hexdigit()) {
+                hex.push_str(chunk);
+                continue;
+            }
+            if in_output && let Some(mon) = monitors.last_mut() {
+                mon.edid = decode_hex(hex).as_deref().and_then(Edid::parse);
+            }
+            edid = None;
+        }
+
+        if line.trim() == "EDID:" {
+            edid = Some(String::new());
+            continue;
+        }
+
+        // The current mode, e.g. "   3840x2160     60.00*+", is in device
+        // pixels; the geometry differs from it when the output is scaled.
+        if in_output
+            && line.starts_with("   ")
+            && line.contains('*')
+            && let Some(mon) = monitors.last_mut()
+            && let Some((w, h)) = line.split_whitespace().next().and_then(parse_mode)
+        {
+            // Rotated outputs report the mode unrotated.
+            let w = if (w > h) == (mon.width > mon.height) {
+                w
+            } else {
+                h
+            };
+            mon.scale = f64::from(w) / f64::from(mon.width);
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) {
+            in_output = false;
+        }
+
         // Match lines like: "HDMI-0 connected 3840x2160+3840+0 ..."
         // or "DP-4 connected primary 3840x2160+0+0 ..."
         if !line.contains(" connected ") || line.contains(" disconnected ") {
@@ -33,9 +216,10 @@ pub fn detect() -> Result<Vec<Monitor>, String> {
         }
 
         // Find the geometry token: WxH+X+Y
-        let Some(geom) = line.split_whitespace().find(|tok| {
-            tok.contains('x') && tok.contains('+')
-        }) else {
+        let Some(geom) = line
+            .split_whitespace()
+            .find(|tok| tok.contains('x') && tok.contains('+'))
+        else {
             continue;
         };
 
@@ -43,15 +227,197 @@ pub fn detect() -> Result<Vec<Monitor>, String> {
 
         if let Some(mon) = parse_geometry(&name, geom) {
             monitors.push(mon);
+            in_output = true;
         }
     }
+    if in_output
+        && let Some(hex) = edid
+        && let Some(mon) = monitors.last_mut()
+    {
+        mon.edid = decode_hex(&hex).as_deref().and_then(Edid::parse);
+    }
 
-    if monitors.is_empty() {
-        return Err("no connected monitors found".into());
+    monitors.sort_by_key(|m| m.x);
+    monitors
+}
+
+/// Parse a mode name like `1920x1080` or `1920x1080i`.
+fn parse_mode(mode: &str) -> Option<(u32, u32)> {
+    let (w, h) = mode.split_once('x')?;
+    let h = h.trim_end_matches(|c: char| !c.is_ascii_digit());
+    Some((w.parse().ok()?, h.parse().ok()?))
+}
+
+#[derive(Deserialize)]
+struct SwayOutput {
+    name: String,
+    #[serde(default)]
+    active: bool,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    serial: String,
+    rect: SwayRect,
+    #[serde(default)]
+    scale: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct SwayRect {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+/// Parse `swaymsg -t get_outputs --raw`, where `rect` is logical.
+fn parse_sway(json: &str) -> Result<Vec<Monitor>, String> {
+    let outputs: Vec<SwayOutput> =
+        serde_json::from_str(json).map_err(|e| format!("failed to parse sway outputs: {e}"))?;
+    let placed = outputs
+        .into_iter()
+        .filter(|o| o.active)
+        .map(|o| {
+            let monitor = Monitor {
+                name: o.name.clone(),
+                connector: o.name,
+                width: o.rect.width,
+                height: o.rect.height,
+                x: 0,
+                y: 0,
+                scale: o.scale.filter(|s| *s > 0.0).unwrap_or(1.0),
+                edid: compositor_edid(o.make, o.model, o.serial),
+                mirrors: Vec::new(),
+            };
+            (monitor, o.rect.x, o.rect.y)
+        })
+        .collect();
+    Ok(place(placed))
+}
+
+#[derive(Deserialize)]
+struct HyprlandMonitor {
+    name: String,
+    #[serde(default)]
+    disabled: bool,
+    #[serde(default)]
+    make: String,
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    serial: String,
+    width: u32,
+    height: u32,
+    x: i32,
+    y: i32,
+    scale: f64,
+    #[serde(default)]
+    transform: u8,
+}
+
+/// Parse `hyprctl monitors -j`, where the size is in device pixels and the
+/// position is logical.
+fn parse_hyprland(json: &str) -> Result<Vec<Monitor>, String> {
+    let outputs: Vec<HyprlandMonitor> = serde_json::from_str(json)
+        .map_err(|e| format!("failed to parse Hyprland monitors: {e}"))?;
+    let placed = outputs
+        .into_iter()
+        .filter(|o| !o.disabled)
+        .map(|o| {
+            let scale = if o.scale > 0.0 { o.scale } else { 1.0 };
+            // Odd transforms rotate by 90 or 270 degrees.
+            let (w, h) = if o.transform % 2 == 1 {
+                (o.height, o.width)
+            } else {
+                (o.width, o.height)
+            };
+            let logical = |v: u32| (f64::from(v) / scale).round() as u32;
+            let monitor = Monitor {
+                name: o.name.clone(),
+                connector: o.name,
+                width: logical(w),
+                height: logical(h),
+                x: 0,
+                y: 0,
+                scale,
+                edid: compositor_edid(o.make, o.model, o.serial),
+                mirrors: Vec::new(),
+            };
+            (monitor, o.x, o.y)
+        })
+        .collect();
+    Ok(place(placed))
+}
+
+/// Compositors report the EDID fields as text, with "Unknown" for missing ones.
+fn compositor_edid(make: String, model: String, serial: String) -> Option<Edid> {
+    let known = |s: String| if s == "Unknown" { String::new() } else { s };
+    let (manufacturer, model) = (known(make), known(model));
+    if manufacturer.is_empty() && model.is_empty() {
+        return None;
     }
+    Some(Edid {
+        manufacturer,
+        model,
+        serial: known(serial),
+    })
+}
 
+/// Shift compositor positions, which may be negative, so the leftmost and
+/// topmost outputs start at zero.
+fn place(outputs: Vec<(Monitor, i32, i32)>) -> Vec<Monitor> {
+    let min_x = outputs.iter().map(|(_, x, _)| *x).min().unwrap_or(0);
+    let min_y = outputs.iter().map(|(_, _, y)| *y).min().unwrap_or(0);
+    let mut monitors: Vec<Monitor> = outputs
+        .into_iter()
+        .map(|(mut m, x, y)| {
+            m.x = x.abs_diff(min_x);
+            m.y = y.abs_diff(min_y);
+            m
+        })
+        .collect();
     monitors.sort_by_key(|m| m.x);
-    Ok(monitors)
+    monitors
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Identify a monitor setup by its displays and resolutions, ignoring their order.
+pub fn fingerprint<'a>(monitors: impl IntoIterator<Item = &'a Monitor>) -> String {
+    let mut outputs: Vec<String> = monitors
+        .into_iter()
+        .map(|m| format!("{}:{}x{}", m.id(), m.width, m.height))
+        .collect();
+    outputs.sort();
+    outputs.join(",")
+}
+
+/// Describe the full geometry of a setup in slot order: unlike `fingerprint`,
+/// it changes when outputs move, swap places, rescale or start mirroring.
+pub fn arrangement(monitors: &[Monitor]) -> String {
+    monitors
+        .iter()
+        .map(|m| {
+            format!(
+                "{}:{}x{}+{}+{}@{}[{}]",
+                m.id(),
+                m.width,
+                m.height,
+                m.x,
+                m.y,
+                m.scale,
+                m.mirrors.join(" ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 fn parse_geometry(name: &str, geom: &str) -> Option<Monitor> {
@@ -62,9 +428,181 @@ fn parse_geometry(name: &str, geom: &str) -> Option<Monitor> {
 
     Some(Monitor {
         name: name.to_string(),
+        connector: name.to_string(),
         width: w.parse().ok()?,
         height: h.parse().ok()?,
         x: x.parse().ok()?,
         y: y.parse().ok()?,
+        scale: 1.0,
+        edid: None,
+        mirrors: Vec::new(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_ignores_order_and_position() {
+        let a = [
+            Monitor::test("DP-4", 3840, 2160, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 3840),
+        ];
+        let b = [
+            Monitor::test("HDMI-0", 1920, 1080, 0),
+            Monitor::test("DP-4", 3840, 2160, 1920),
+        ];
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+    }
+
+    #[test]
+    fn arrangement_changes_when_outputs_move() {
+        let a = [
+            Monitor::test("DP-4", 1920, 1080, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 1920),
+        ];
+        let mut swapped = a.clone();
+        swapped[0].x = 1920;
+        swapped[1].x = 0;
+        swapped.sort_by_key(|m| m.x);
+        assert_eq!(fingerprint(&a), fingerprint(&swapped));
+        assert_ne!(arrangement(&a), arrangement(&swapped));
+
+        let mut moved = a.clone();
+        moved[1].y = 200;
+        assert_ne!(arrangement(&a), arrangement(&moved));
+    }
+
+    #[test]
+    fn fingerprint_differs_by_resolution() {
+        let a = [Monitor::test("eDP-1", 1920, 1080, 0)];
+        let b = [Monitor::test("eDP-1", 2560, 1440, 0)];
+        assert_ne!(fingerprint(&a), fingerprint(&b));
+    }
+
+    #[test]
+    fn merges_cloned_outputs() {
+        let monitors = vec![
+            Monitor::test("DP-4", 1920, 1080, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 0),
+            Monitor::test("DP-5", 1920, 1080, 1920),
+            Monitor::test("eDP-1", 1280, 720, 0),
+        ];
+        let merged = merge_mirrors(monitors);
+        let names: Vec<_> = merged
+            .iter()
+            .map(|m| (m.name.as_str(), m.mirrors.clone()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("DP-4", vec!["HDMI-0".to_string()]),
+                ("DP-5", vec![]),
+                ("eDP-1", vec![]),
+            ]
+        );
+    }
+
+    /// A minimal EDID: Dell product 0x41b5 with name and serial descriptors.
+    fn edid_bytes() -> Vec<u8> {
+        let mut bytes = vec![0u8; 128];
+        bytes[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        bytes[8..16].copy_from_slice(&[0x10, 0xac, 0xb5, 0x41, 0x4c, 0x38, 0x39, 0x30]);
+        bytes[54] = 0x01; // first descriptor is a detailed timing
+        bytes[72..77].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+        bytes[77..90].copy_from_slice(b"DELL U2720Q\n ");
+        bytes[90..95].copy_from_slice(&[0, 0, 0, 0xff, 0]);
+        bytes[95..108].copy_from_slice(b"7GR1W23\n     ");
+        bytes
+    }
+
+    #[test]
+    fn parses_edid_descriptors() {
+        let edid = Edid::parse(&edid_bytes()).unwrap();
+        assert_eq!(edid.manufacturer, "DEL");
+        assert_eq!(edid.model, "DELL U2720Q");
+        assert_eq!(edid.serial, "7GR1W23");
+    }
+
+    #[test]
+    fn edid_without_descriptors_uses_codes() {
+        let mut bytes = edid_bytes();
+        bytes[72..108].fill(0x01);
+        let edid = Edid::parse(&bytes).unwrap();
+        assert_eq!(edid.model, "41b5");
+        assert_eq!(edid.serial, format!("{}", 0x3039384c));
+        assert!(Edid::parse(&bytes[..64]).is_none());
+    }
+
+    #[test]
+    fn reads_edid_from_xrandr_props() {
+        let hex: String = edid_bytes().iter().map(|b| format!("{b:02x}")).collect();
+        let block: String = hex
+            .as_bytes()
+            .chunks(32)
+            .map(|c| format!("\t\t{}\n", std::str::from_utf8(c).unwrap()))
+            .collect();
+        let stdout = format!(
+            "Screen 0: minimum 8 x 8, current 5760 x 2160, maximum 32767 x 32767\n\
+             HDMI-0 connected 1920x1080+3840+0 (normal left inverted right) 527mm x 296mm\n\
+             \tnon-desktop: 0\n\
+             DP-4 connected primary 3840x2160+0+0 (normal left inverted right) 597mm x 336mm\n\
+             \tEDID: \n{block}\
+             \tnon-desktop: 0\n\
+             \x20  3840x2160     60.00*+\n\
+             DP-5 disconnected (normal left inverted right x axis y axis)\n"
+        );
+        let monitors = parse(&stdout);
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[0].name, "DP-4");
+        assert_eq!(monitors[0].id(), "DEL:DELL U2720Q:7GR1W23");
+        assert_eq!(monitors[1].name, "HDMI-0");
+        assert_eq!(monitors[1].id(), "HDMI-0");
+    }
+
+    #[test]
+    fn scale_from_current_mode() {
+        let stdout = "DP-4 connected primary 3840x2160+0+0 (normal left inverted right) 597mm x 336mm\n\
+                      \x20  1920x1080     60.00*+\n\
+                      HDMI-0 connected 1080x1920+3840+0 left (normal left inverted right) 527mm x 296mm\n\
+                      \x20  1920x1080     60.00*+\n";
+        let monitors = parse(stdout);
+        assert_eq!(monitors[0].scale, 0.5);
+        assert_eq!(monitors[0].physical_size(), (1920, 1080));
+        assert_eq!(monitors[1].scale, 1.0);
+    }
+
+    #[test]
+    fn parses_sway_outputs() {
+        let json = r#"[
+            {"name": "eDP-1", "active": true, "make": "Sharp", "model": "0x14D1", "serial": "Unknown",
+             "rect": {"x": -1280, "y": 0, "width": 1280, "height": 800}, "scale": 2.0},
+            {"name": "DP-1", "active": true, "make": "Dell Inc.", "model": "DELL U2720Q", "serial": "7GR1W23",
+             "rect": {"x": 0, "y": 0, "width": 2560, "height": 1440}, "scale": 1.5},
+            {"name": "HDMI-A-1", "active": false, "rect": {"x": 0, "y": 0, "width": 0, "height": 0}}
+        ]"#;
+        let monitors = parse_sway(json).unwrap();
+        assert_eq!(monitors.len(), 2);
+        assert_eq!((monitors[0].name.as_str(), monitors[0].x), ("eDP-1", 0));
+        assert_eq!(monitors[0].physical_size(), (2560, 1600));
+        assert_eq!(monitors[0].id(), "Sharp:0x14D1:");
+        assert_eq!((monitors[1].x, monitors[1].width), (1280, 2560));
+        assert_eq!(monitors[1].physical_size(), (3840, 2160));
+    }
+
+    #[test]
+    fn parses_hyprland_monitors() {
+        let json = r#"[
+            {"name": "DP-1", "make": "Dell Inc.", "model": "DELL U2720Q", "serial": "7GR1W23",
+             "width": 3840, "height": 2160, "x": 0, "y": 0, "scale": 2.0, "transform": 0},
+            {"name": "HDMI-A-1", "make": "", "model": "", "serial": "",
+             "width": 1920, "height": 1080, "x": 1920, "y": 0, "scale": 1.0, "transform": 1}
+        ]"#;
+        let monitors = parse_hyprland(json).unwrap();
+        assert_eq!((monitors[0].width, monitors[0].height), (1920, 1080));
+        assert_eq!(monitors[0].physical_size(), (3840, 2160));
+        assert_eq!((monitors[1].width, monitors[1].height), (1080, 1920));
+        assert_eq!(monitors[1].id(), "HDMI-A-1");
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::monitors::Monitor;
+use crate::{config, wallpaper};
+
+/// Version of the overrides file format, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// Manual corrections to the detected monitors, read from `monitors.ron`.
+#[derive(Debug, Deserialize)]
+pub struct Overrides {
+    version: u32,
+    #[serde(default)]
+    outputs: Vec<Output>,
+    /// Slot order by (renamed) output name or EDID identity; outputs not
+    /// listed follow left-to-right.
+    #[serde(default)]
+    order: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Output {
+    /// Connector name or EDID identity (see `Monitor::id`) of the output.
+    output: String,
+    #[serde(default)]
+    rename: Option<String>,
+    #[serde(default)]
+    exclude: bool,
+    #[serde(default)]
+    geometry: Option<Geometry>,
+    /// Physical pixels per logical pixel, when detection gets it wrong.
+    #[serde(default)]
+    scale: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Geometry {
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+}
+
+impl Overrides {
+    /// Rename, drop, move, rescale and reorder `monitors` as configured.
+    pub fn apply(&self, monitors: Vec<Monitor>) -> Vec<Monitor> {
+        let mut result = Vec::new();
+        for mut monitor in monitors {
+            let Some(output) = self
+                .outputs
+                .iter()
+                .find(|o| o.output == monitor.connector || o.output == monitor.id())
+            else {
+                result.push(monitor);
+                continue;
+            };
+            if output.exclude {
+                continue;
+            }
+            if let Some(name) = &output.rename {
+                monitor.name = name.clone();
+            }
+            if let Some(g) = output.geometry {
+                (monitor.width, monitor.height) = (g.width, g.height);
+                (monitor.x, monitor.y) = (g.x, g.y);
+            }
+            if let Some(scale) = output.scale {
+                monitor.scale = scale;
+            }
+            result.push(monitor);
+        }
+
+        result.sort_by_key(|m| m.x);
+        result.sort_by_key(|m| {
+            self.order
+                .iter()
+                .position(|o| *o == m.name || *o == m.id())
+                .unwrap_or(usize::MAX)
+        });
+        result
+    }
+}
+
+/// Load the overrides file; `None` when there is none.
+pub fn load() -> Result<Option<Overrides>, String> {
+    let path = path();
+    let Some(overrides) = config::read::<Overrides>(&path)? else {
+        return Ok(None);
+    };
+    config::check_version(&path, overrides.version, VERSION)?;
+    Ok(Some(overrides))
+}
+
+pub fn path() -> PathBuf {
+    wallpaper::dirs_config().join("nitrohydra/monitors.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(monitors: &[Monitor]) -> Vec<&str> {
+        monitors.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    #[test]
+    fn renames_excludes_and_moves() {
+        let overrides: Overrides = ron::from_str(
+            r#"(
+                version: 1,
+                outputs: [
+                    (output: "VNC-0", exclude: true),
+                    (output: "HDMI-0", rename: Some("TV"), geometry: Some((width: 1280, height: 720, x: 0, y: 0))),
+                    (output: "DP-4", geometry: Some((width: 1920, height: 1080, x: 1280, y: 0)), scale: Some(2.0)),
+                ],
+            )"#,
+        )
+        .unwrap();
+        let monitors = [
+            Monitor::test("DP-4", 1920, 1080, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 1920),
+            Monitor::test("VNC-0", 1920, 1080, 3840),
+        ];
+        let result = overrides.apply(monitors.to_vec());
+        assert_eq!(names(&result), ["TV", "DP-4"]);
+        assert_eq!(result[0].connector, "HDMI-0");
+        assert_eq!((result[0].width, result[0].height), (1280, 720));
+        assert_eq!(result[1].x, 1280);
+        assert_eq!(result[1].physical_size(), (3840, 2160));
+    }
+
+    #[test]
+    fn order_puts_listed_outputs_first() {
+        let overrides: Overrides = ron::from_str(r#"(version: 1, order: ["HDMI-0"])"#).unwrap();
+        let monitors = [
+            Monitor::test("DP-1", 1920, 1080, 0),
+            Monitor::test("DP-4", 1920, 1080, 1920),
+            Monitor::test("HDMI-0", 1920, 1080, 3840),
+        ];
+        let result = overrides.apply(monitors.to_vec());
+        assert_eq!(names(&result), ["HDMI-0", "DP-1", "DP-4"]);
+    }
+}
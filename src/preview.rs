@@ -1,11 +1,10 @@
-use std::path::PathBuf;
 use std::sync::mpsc;
 use std::time::Instant;
 
 use eframe::egui;
 
-use crate::monitors::Monitor;
-use crate::{cache, wallpaper};
+use crate::cache;
+use crate::wallpaper::{self, Assignment};
 
 pub struct PreviewJob {
     rx: Option<mpsc::Receiver<Result<egui::ColorImage, String>>>,
@@ -24,7 +23,7 @@ impl PreviewJob {
         }
     }
 
-    pub fn start(&mut self, assignments: Vec<(PathBuf, Monitor)>, ctx: &egui::Context) {
+    pub fn start(&mut self, assignments: Vec<Assignment>, ctx: &egui::Context) {
         let (tx, rx) = mpsc::channel();
         let ctx = ctx.clone();
         std::thread::spawn(move || {
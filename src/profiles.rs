@@ -0,0 +1,277 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+use crate::monitors::Monitor;
+use crate::wallpaper::{self, Assignment, Framing};
+
+/// Version of the profiles file format, bumped on incompatible changes.
+const VERSION: u32 = 1;
+
+/// A named set of images per monitor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub slots: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Slot {
+    /// Connector name of the monitor, e.g. `DP-4`.
+    pub monitor: String,
+    /// EDID identity of the monitor (see `Monitor::id`), when it reported one.
+    #[serde(default)]
+    pub edid: Option<String>,
+    pub path: PathBuf,
+    pub framing: Framing,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProfilesFile {
+    version: u32,
+    profiles: Vec<Profile>,
+}
+
+impl Profile {
+    pub fn from_assignments(name: String, assignments: &[Assignment]) -> Self {
+        Self {
+            name,
+            slots: slots(assignments),
+        }
+    }
+
+    pub fn assignments(&self, monitors: &[Monitor]) -> Result<Vec<Assignment>, String> {
+        let assignments = assign(&self.slots, monitors);
+        if assignments.is_empty() {
+            return Err(format!("profile '{}' has no images", self.name));
+        }
+        Ok(assignments)
+    }
+}
+
+/// Map slots onto the detected monitors by EDID identity, then by connector
+/// name. Slots whose monitor is gone fill the remaining monitors left-to-right.
+/// The result is in the slot order of `monitors`.
+pub fn assign(slots: &[Slot], monitors: &[Monitor]) -> Vec<Assignment> {
+    // Identical panels without a serial share an identity, which then tells
+    // nothing about which is which; those go by connector.
+    let ids: Vec<Option<String>> = monitors
+        .iter()
+        .map(|m| m.edid.as_ref().map(|e| e.id()))
+        .collect();
+    let unique_id = |m: usize| {
+        ids[m]
+            .as_ref()
+            .filter(|id| ids.iter().flatten().filter(|other| other == id).count() == 1)
+    };
+
+    // Indices into `monitors`, so the result can keep their order.
+    let mut free: Vec<usize> = (0..monitors.len()).collect();
+    let mut assigned = Vec::new();
+    let mut unmatched = Vec::new();
+    for slot in slots {
+        let by_edid = slot
+            .edid
+            .as_ref()
+            .and_then(|id| free.iter().position(|&m| unique_id(m) == Some(id)));
+        match by_edid {
+            Some(i) => assigned.push((slot, free.remove(i))),
+            None => unmatched.push(slot),
+        }
+    }
+    let mut leftover = Vec::new();
+    for slot in unmatched {
+        let by_connector = free
+            .iter()
+            .position(|&m| monitors[m].connector == slot.monitor);
+        match by_connector {
+            Some(i) => assigned.push((slot, free.remove(i))),
+            None => leftover.push(slot),
+        }
+    }
+    assigned.extend(leftover.into_iter().zip(free));
+    assigned.sort_by_key(|&(_, monitor)| monitor);
+
+    assigned
+        .into_iter()
+        .map(|(slot, monitor)| Assignment {
+            path: slot.path.clone(),
+            monitor: monitors[monitor].clone(),
+            framing: slot.framing,
+        })
+        .collect()
+}
+
+pub fn slots(assignments: &[Assignment]) -> Vec<Slot> {
+    assignments
+        .iter()
+        .map(|a| Slot {
+            monitor: a.monitor.connector.clone(),
+            edid: a.monitor.edid.as_ref().map(|e| e.id()),
+            path: a.path.clone(),
+            framing: a.framing,
+        })
+        .collect()
+}
+
+/// Load saved profiles; a missing file means there are none yet.
+pub fn load() -> Result<Vec<Profile>, String> {
+    let path = path();
+    let Some(file) = config::read::<ProfilesFile>(&path)? else {
+        return Ok(Vec::new());
+    };
+    config::check_version(&path, file.version, VERSION)?;
+    Ok(file.profiles)
+}
+
+pub fn save(profiles: &[Profile]) -> Result<(), String> {
+    let file = ProfilesFile {
+        version: VERSION,
+        profiles: profiles.to_vec(),
+    };
+    config::write(&path(), &file)
+}
+
+pub fn find<'a>(profiles: &'a [Profile], name: &str) -> Option<&'a Profile> {
+    profiles.iter().find(|p| p.name == name)
+}
+
+fn path() -> PathBuf {
+    wallpaper::dirs_config().join("nitrohydra/profiles.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitors::Edid;
+
+    fn profile(monitors: &[&str]) -> Profile {
+        Profile {
+            name: "work".into(),
+            slots: monitors
+                .iter()
+                .map(|m| Slot {
+                    monitor: m.to_string(),
+                    edid: None,
+                    path: PathBuf::from(format!("/{m}.jpg")),
+                    framing: Framing::default(),
+                })
+                .collect(),
+        }
+    }
+
+    fn paths(assignments: &[Assignment]) -> Vec<(String, PathBuf)> {
+        assignments
+            .iter()
+            .map(|a| (a.monitor.name.clone(), a.path.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn assigns_by_monitor_name() {
+        let monitors = [
+            Monitor::test("DP-4", 1920, 1080, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 1920),
+        ];
+        let assignments = profile(&["HDMI-0", "DP-4"]).assignments(&monitors).unwrap();
+        assert_eq!(
+            paths(&assignments),
+            vec![
+                ("DP-4".into(), PathBuf::from("/DP-4.jpg")),
+                ("HDMI-0".into(), PathBuf::from("/HDMI-0.jpg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_edid_falls_back_to_connector() {
+        // Two identical panels without a serial number.
+        let edid = Edid {
+            manufacturer: "AOC".into(),
+            model: "24G2".into(),
+            serial: String::new(),
+        };
+        let mut left = Monitor::test("DP-4", 1920, 1080, 0);
+        left.edid = Some(edid.clone());
+        let mut right = Monitor::test("DP-5", 1920, 1080, 1920);
+        right.edid = Some(edid.clone());
+
+        let mut profile = profile(&["DP-5", "DP-4"]);
+        for slot in &mut profile.slots {
+            slot.edid = Some(edid.id());
+        }
+
+        let assignments = profile.assignments(&[left, right]).unwrap();
+        assert_eq!(
+            paths(&assignments),
+            vec![
+                ("DP-4".into(), PathBuf::from("/DP-4.jpg")),
+                ("DP-5".into(), PathBuf::from("/DP-5.jpg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_monitors_fill_remaining_left_to_right() {
+        let monitors = [
+            Monitor::test("DP-1", 1920, 1080, 0),
+            Monitor::test("HDMI-0", 1920, 1080, 1920),
+        ];
+        let assignments = profile(&["HDMI-0", "DP-4"]).assignments(&monitors).unwrap();
+        assert_eq!(
+            paths(&assignments),
+            vec![
+                ("DP-1".into(), PathBuf::from("/DP-4.jpg")),
+                ("HDMI-0".into(), PathBuf::from("/HDMI-0.jpg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn edid_wins_over_connector_name() {
+        let edid = |serial: &str| Edid {
+            manufacturer: "DEL".into(),
+            model: "DELL U2720Q".into(),
+            serial: serial.into(),
+        };
+        // The two displays swapped connectors since the profile was saved.
+        let mut left = Monitor::test("DP-4", 1920, 1080, 0);
+        left.edid = Some(edid("B"));
+        let mut right = Monitor::test("DP-5", 1920, 1080, 1920);
+        right.edid = Some(edid("A"));
+
+        let mut profile = profile(&["DP-4", "DP-5"]);
+        profile.slots[0].edid = Some(edid("A").id());
+        profile.slots[1].edid = Some(edid("B").id());
+
+        let assignments = profile.assignments(&[left, right]).unwrap();
+        assert_eq!(
+            paths(&assignments),
+            vec![
+                ("DP-4".into(), PathBuf::from("/DP-5.jpg")),
+                ("DP-5".into(), PathBuf::from("/DP-4.jpg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_slots_without_edid() {
+        let slot: Slot = ron::from_str(
+            r#"(monitor: "DP-4", path: "/a.jpg", framing: (fit: Cover, crop: (0.5, 0.5)))"#,
+        )
+        .unwrap();
+        assert_eq!(slot.edid, None);
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let file = ProfilesFile {
+            version: VERSION,
+            profiles: vec![profile(&["DP-4"])],
+        };
+        let text = ron::to_string(&file).unwrap();
+        let parsed: ProfilesFile = ron::from_str(&text).unwrap();
+        assert_eq!(parsed.profiles[0].slots[0].monitor, "DP-4");
+    }
+}
@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use rayon::prelude::*;
+
+use crate::monitors::Monitor;
+use crate::wallpaper::Assignment;
+
+pub struct Candidate<'a> {
+    pub path: &'a Path,
+    pub size: Option<[u32; 2]>,
+}
+
+/// Pick one candidate per monitor, returning candidate indices in monitor order.
+///
+/// Recently used images are skipped unless there are not enough others. With
+/// `prefer_fit`, each monitor picks from the quarter of images whose aspect ratio
+/// is closest to its own.
+pub fn pick(
+    candidates: &[Candidate],
+    monitors: &[Monitor],
+    recent: &[PathBuf],
+    prefer_fit: bool,
+    rng: &mut impl Rng,
+) -> Vec<usize> {
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let fresh: Vec<usize> = (0..candidates.len())
+        .filter(|&i| !recent.iter().any(|r| r == candidates[i].path))
+        .collect();
+    let mut pool = if fresh.len() >= monitors.len() {
+        fresh
+    } else {
+        (0..candidates.len()).collect()
+    };
+
+    let mut picked = Vec::new();
+    for monitor in monitors {
+        if pool.is_empty() {
+            pool = (0..candidates.len()).collect();
+        }
+        if prefer_fit {
+            pool.sort_by(|&a, &b| {
+                fit_score(&candidates[a], monitor).total_cmp(&fit_score(&candidates[b], monitor))
+            });
+        }
+        let best = if prefer_fit {
+            pool.len().div_ceil(4)
+        } else {
+            pool.len()
+        };
+        let &choice = pool[..best].choose(rng).unwrap();
+        pool.retain(|&i| i != choice);
+        picked.push(choice);
+    }
+    picked
+}
+
+fn fit_score(candidate: &Candidate, monitor: &Monitor) -> f64 {
+    match candidate.size {
+        Some(size) => aspect_distance(size, monitor),
+        None => f64::INFINITY,
+    }
+}
+
+/// How far the image aspect ratio is from the monitor's; 0 is a perfect fit.
+pub fn aspect_distance([w, h]: [u32; 2], monitor: &Monitor) -> f64 {
+    let image = f64::from(w) / f64::from(h.max(1));
+    let screen = f64::from(monitor.width) / f64::from(monitor.height.max(1));
+    (image / screen).ln().abs()
+}
+
+/// Pick images from `dir` for every monitor, reading dimensions only when `prefer_fit` is set.
+pub fn pick_from_dir(
+    dir: &Path,
+    monitors: &[Monitor],
+    prefer_fit: bool,
+) -> Result<Vec<Assignment>, String> {
+    let paths =
+        crate::loader::scan(dir).map_err(|e| format!("failed to read {}: {e}", dir.display()))?;
+    if paths.is_empty() {
+        return Err(format!("no images found in {}", dir.display()));
+    }
+    let recent = crate::history::recent();
+    Ok(pick_from_paths(&paths, monitors, &recent, prefer_fit))
+}
+
+/// Like `pick_from_dir`, but for an already collected list of images.
+pub fn pick_from_paths(
+    paths: &[PathBuf],
+    monitors: &[Monitor],
+    recent: &[PathBuf],
+    prefer_fit: bool,
+) -> Vec<Assignment> {
+    let sizes: Vec<Option<[u32; 2]>> = if prefer_fit {
+        paths
+            .par_iter()
+            .map(|p| crate::loader::dimensions(p).ok().map(|(w, h)| [w, h]))
+            .collect()
+    } else {
+        vec![None; paths.len()]
+    };
+    let candidates: Vec<Candidate> = paths
+        .iter()
+        .zip(sizes)
+        .map(|(path, size)| Candidate { path, size })
+        .collect();
+
+    let picked = pick(&candidates, monitors, recent, prefer_fit, &mut rand::rng());
+    picked
+        .into_iter()
+        .zip(monitors)
+        .map(|(i, m)| Assignment::new(paths[i].clone(), m.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn paths(n: usize) -> Vec<PathBuf> {
+        (0..n).map(|i| PathBuf::from(format!("{i}.jpg"))).collect()
+    }
+
+    #[test]
+    fn picks_distinct_images() {
+        let paths = paths(2);
+        let candidates: Vec<Candidate> = paths
+            .iter()
+            .map(|path| Candidate { path, size: None })
+            .collect();
+        let monitors = [
+            Monitor::test("M", 1920, 1080, 0),
+            Monitor::test("M", 1920, 1080, 0),
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut picked = pick(&candidates, &monitors, &[], false, &mut rng);
+        picked.sort();
+        assert_eq!(picked, vec![0, 1]);
+    }
+
+    #[test]
+    fn repeats_when_not_enough_images() {
+        let paths = paths(1);
+        let candidates = [Candidate {
+            path: &paths[0],
+            size: None,
+        }];
+        let monitors = [
+            Monitor::test("M", 1920, 1080, 0),
+            Monitor::test("M", 1920, 1080, 0),
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(
+            pick(&candidates, &monitors, &[], false, &mut rng),
+            vec![0, 0]
+        );
+    }
+
+    #[test]
+    fn skips_recent() {
+        let paths = paths(3);
+        let candidates: Vec<Candidate> = paths
+            .iter()
+            .map(|path| Candidate { path, size: None })
+            .collect();
+        let monitors = [Monitor::test("M", 1920, 1080, 0)];
+        let recent = vec![paths[0].clone(), paths[1].clone()];
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(
+            pick(&candidates, &monitors, &recent, false, &mut rng),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn prefers_matching_aspect() {
+        let paths = paths(4);
+        let sizes = [[1080, 1920], [1920, 1080], [1000, 1000], [800, 1200]];
+        let candidates: Vec<Candidate> = paths
+            .iter()
+            .zip(sizes)
+            .map(|(path, size)| Candidate {
+                path,
+                size: Some(size),
+            })
+            .collect();
+        let monitors = [
+            Monitor::test("M", 1080, 1920, 0),
+            Monitor::test("M", 3840, 2160, 0),
+        ];
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(
+            pick(&candidates, &monitors, &[], true, &mut rng),
+            vec![0, 1]
+        );
+    }
+}
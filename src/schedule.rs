@@ -0,0 +1,467 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeDelta, TimeZone, Timelike, Utc};
+
+use crate::slideshow::{self, Source};
+use crate::wallpaper;
+
+/// How often a running schedule re-checks which rule is active.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// A list of rules; the first one matching the current moment decides the wallpaper source.
+///
+/// ```text
+/// location 52.52 13.40        # latitude and longitude for sunrise/sunset
+/// interval 60                 # optional: also re-pick every 60 minutes
+/// mon-fri 09:00-18:00 ~/Wallpapers/work
+/// *       sunset-sunrise ~/Wallpapers/dark
+/// *       *       ~/Wallpapers
+/// ```
+pub struct Schedule {
+    location: Option<Location>,
+    interval: Option<Duration>,
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Copy)]
+struct Location {
+    lat: f64,
+    lon: f64,
+}
+
+struct Rule {
+    line: String,
+    /// Matching weekdays, Monday first.
+    days: [bool; 7],
+    /// Start and end of the active span; `None` means the whole day.
+    span: Option<(Time, Time)>,
+    source: Source,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Time {
+    At(NaiveTime),
+    Sunrise,
+    Sunset,
+}
+
+impl Time {
+    fn is_sun(self) -> bool {
+        !matches!(self, Self::At(_))
+    }
+}
+
+/// Where the sun is on one date at one place.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Sun {
+    /// Sunrise and sunset.
+    RisesAndSets(DateTime<Utc>, DateTime<Utc>),
+    /// Polar day: above the horizon all day.
+    AlwaysUp,
+    /// Polar night: below the horizon all day.
+    AlwaysDown,
+}
+
+impl Schedule {
+    pub fn load(file: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(file)
+            .map_err(|e| format!("failed to read {}: {e}", file.display()))?;
+        Self::parse(&contents).map_err(|e| format!("{}: {e}", file.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut schedule = Self {
+            location: None,
+            interval: None,
+            rules: Vec::new(),
+        };
+        let mut uses_sun = false;
+
+        for (n, line) in contents.lines().enumerate() {
+            let err = |msg: String| format!("line {}: {msg}", n + 1);
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((keyword, rest)) = split_word(line) else {
+                continue;
+            };
+            match keyword {
+                "location" => {
+                    let coords: Vec<f64> = rest
+                        .split_whitespace()
+                        .map(|v| {
+                            v.parse()
+                                .map_err(|_| err(format!("invalid coordinate '{v}'")))
+                        })
+                        .collect::<Result<_, _>>()?;
+                    let [lat, lon] = coords[..] else {
+                        return Err(err("expected 'location <latitude> <longitude>'".into()));
+                    };
+                    schedule.location = Some(Location { lat, lon });
+                }
+                "interval" => {
+                    let minutes: u64 = rest
+                        .parse()
+                        .ok()
+                        .filter(|&m| m > 0)
+                        .ok_or_else(|| err(format!("invalid interval '{rest}'")))?;
+                    schedule.interval = Some(Duration::from_secs(minutes * 60));
+                }
+                days => {
+                    let (span, path) = split_word(rest)
+                        .filter(|(_, path)| !path.is_empty())
+                        .ok_or_else(|| err("expected '<days> <from>-<to> <path>'".into()))?;
+                    let span = parse_span(span).map_err(err)?;
+                    uses_sun |= span.is_some_and(|(from, to)| from.is_sun() || to.is_sun());
+                    schedule.rules.push(Rule {
+                        line: line.to_string(),
+                        days: parse_days(days).map_err(err)?,
+                        span,
+                        source: Source::from_path(&expand_home(path)),
+                    });
+                }
+            }
+        }
+
+        if uses_sun && schedule.location.is_none() {
+            return Err("sunrise and sunset need a 'location <latitude> <longitude>' line".into());
+        }
+        Ok(schedule)
+    }
+
+    /// Index of the first rule matching `at`.
+    fn active<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> Option<usize> {
+        self.rules.iter().position(|rule| self.matches(rule, at))
+    }
+
+    fn matches<Tz: TimeZone>(&self, rule: &Rule, at: &DateTime<Tz>) -> bool {
+        let weekday = at.weekday().num_days_from_monday() as usize;
+        let Some((from, to)) = rule.span else {
+            return rule.days[weekday];
+        };
+
+        let (mut sunrise, mut sunset) = (NaiveTime::MIN, NaiveTime::MIN);
+        if from.is_sun() || to.is_sun() {
+            let location = self.location.expect("checked while parsing");
+            match sun_times(at.date_naive(), location) {
+                Sun::RisesAndSets(rise, set) => {
+                    sunrise = rise.with_timezone(&at.timezone()).time();
+                    sunset = set.with_timezone(&at.timezone()).time();
+                }
+                polar => {
+                    // Without sunrise or sunset, a span by daylight lasts all day
+                    // while the sun stays up, and a span by night while it stays down.
+                    let by_daylight = from == Time::Sunrise || to == Time::Sunset;
+                    return rule.days[weekday] && by_daylight == (polar == Sun::AlwaysUp);
+                }
+            }
+        }
+        let resolve = |time| match time {
+            Time::At(t) => t,
+            Time::Sunrise => sunrise,
+            Time::Sunset => sunset,
+        };
+
+        let now = at.time();
+        let (from, to) = (resolve(from), resolve(to));
+        if from <= to {
+            rule.days[weekday] && from <= now && now < to
+        } else {
+            // Spans past midnight: the early hours belong to the previous day's rule.
+            let yesterday = (weekday + 6) % 7;
+            (rule.days[weekday] && now >= from) || (rule.days[yesterday] && now < to)
+        }
+    }
+
+    /// Moments within `[from, until)` at which the active rule changes, starting with `from`.
+    fn transitions<Tz: TimeZone>(
+        &self,
+        from: DateTime<Tz>,
+        until: DateTime<Tz>,
+    ) -> Vec<(DateTime<Tz>, Option<usize>)> {
+        let mut active = self.active(&from);
+        let mut changes = vec![(from.clone(), active)];
+        let mut at = from
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(from)
+            + TimeDelta::minutes(1);
+        while at < until {
+            let next = self.active(&at);
+            if next != active {
+                changes.push((at.clone(), next));
+                active = next;
+            }
+            at += TimeDelta::minutes(1);
+        }
+        changes
+    }
+}
+
+/// Apply wallpapers from the active rule whenever it changes, until SIGTERM or SIGINT.
+pub fn run(schedule: &Schedule, prefer_fit: bool) -> Result<(), String> {
+    let stop = slideshow::stop_flag()?;
+    let mut current = None;
+    let mut applied_at = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        let active = schedule.active(&Local::now());
+        let due = schedule.interval.is_some_and(|i| applied_at.elapsed() >= i);
+        if current != Some(active) || due {
+            if let Some(index) = active {
+                let rule = &schedule.rules[index];
+                eprintln!("{}  {}", Local::now().format("%a %H:%M"), rule.line);
+                if let Err(e) = slideshow::apply_random(&rule.source, prefer_fit) {
+                    eprintln!("error: {e}");
+                }
+            }
+            current = Some(active);
+            applied_at = Instant::now();
+        }
+        slideshow::sleep(CHECK_INTERVAL, &stop);
+    }
+
+    eprintln!("Schedule stopped.");
+    Ok(())
+}
+
+/// Longest span `dry_run` looks ahead: a year, leap day included.
+pub const MAX_DRY_RUN_HOURS: i64 = 366 * 24;
+
+/// Print which rule would be applied when over the next `hours`, at most
+/// `MAX_DRY_RUN_HOURS`.
+pub fn dry_run(schedule: &Schedule, hours: i64) {
+    if let Some(interval) = schedule.interval {
+        println!(
+            "Images are re-picked every {} minutes while a rule is active.",
+            interval.as_secs() / 60
+        );
+    }
+    let now = Local::now();
+    for (at, active) in schedule.transitions(now, now + TimeDelta::hours(hours)) {
+        let when = at.format("%a %Y-%m-%d %H:%M");
+        match active {
+            Some(index) => {
+                let rule = &schedule.rules[index];
+                let images = match rule.source.images() {
+                    Ok(images) => format!("{} images", images.len()),
+                    Err(e) => format!("error: {e}"),
+                };
+                println!("{when}  {}  ({images})", rule.line);
+            }
+            None => println!("{when}  no rule matches, wallpaper is kept"),
+        }
+    }
+}
+
+pub fn default_path() -> PathBuf {
+    wallpaper::dirs_config().join("nitrohydra/schedule")
+}
+
+/// Sunrise and sunset on `date`, using the sunrise equation with atmospheric refraction.
+fn sun_times(date: NaiveDate, location: Location) -> Sun {
+    const J2000_UNIX: f64 = 946_728_000.0; // 2000-01-01 12:00 UTC
+
+    let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    let days = date.signed_duration_since(j2000).num_days() as f64;
+    let mean_solar = days - location.lon / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_solar).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = mean_solar + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic).sin();
+
+    let decl_sin = ecliptic.sin() * 23.4397_f64.to_radians().sin();
+    let decl_cos = (1.0 - decl_sin * decl_sin).sqrt();
+    let lat = location.lat.to_radians();
+    let cos_hour =
+        ((-0.833_f64).to_radians().sin() - lat.sin() * decl_sin) / (lat.cos() * decl_cos);
+    if cos_hour < -1.0 {
+        return Sun::AlwaysUp;
+    }
+    if cos_hour > 1.0 {
+        return Sun::AlwaysDown;
+    }
+    let hour_angle = cos_hour.acos().to_degrees() / 360.0;
+
+    let to_utc = |day: f64| {
+        let secs = (J2000_UNIX + day * 86_400.0).round() as i64;
+        DateTime::from_timestamp(secs, 0).unwrap_or_default()
+    };
+    Sun::RisesAndSets(to_utc(transit - hour_angle), to_utc(transit + hour_angle))
+}
+
+fn parse_days(spec: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    if spec == "*" {
+        return Ok([true; 7]);
+    }
+    let day = |name: &str| {
+        DAYS.iter()
+            .position(|d| d.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown day '{name}'"))
+    };
+    for part in spec.split(',') {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(part)?, day(part)?),
+        };
+        let mut d = first;
+        loop {
+            days[d] = true;
+            if d == last {
+                break;
+            }
+            d = (d + 1) % 7;
+        }
+    }
+    Ok(days)
+}
+
+fn parse_span(spec: &str) -> Result<Option<(Time, Time)>, String> {
+    if spec == "*" {
+        return Ok(None);
+    }
+    let time = |s: &str| match s {
+        "sunrise" => Ok(Time::Sunrise),
+        "sunset" => Ok(Time::Sunset),
+        _ => NaiveTime::parse_from_str(s, "%H:%M")
+            .map(Time::At)
+            .map_err(|_| format!("invalid time '{s}', expected HH:MM, sunrise or sunset")),
+    };
+    let (from, to) = spec
+        .split_once('-')
+        .ok_or_else(|| format!("invalid time span '{spec}', expected <from>-<to>"))?;
+    Ok(Some((time(from)?, time(to)?)))
+}
+
+fn split_word(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    if s.is_empty() {
+        return None;
+    }
+    let (word, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+    Some((word, rest.trim()))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn at(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    const RULES: &str = "
+        location 52.52 13.40
+        mon-fri 09:00-18:00 /work
+        *       sunset-sunrise /dark  # after dark
+        *       * /all
+    ";
+
+    #[test]
+    fn parse_day_ranges() {
+        let days = parse_days("mon-wed,sat").unwrap();
+        assert_eq!(days, [true, true, true, false, false, true, false]);
+        let wrapped = parse_days("fri-mon").unwrap();
+        assert_eq!(wrapped, [true, false, false, false, true, true, true]);
+        assert!(parse_days("someday").is_err());
+    }
+
+    #[test]
+    fn parse_requires_location_for_sun() {
+        assert!(Schedule::parse("* sunset-sunrise /dark").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_missing_path() {
+        assert!(Schedule::parse("mon 09:00-10:00").is_err());
+    }
+
+    #[test]
+    fn weekday_office_hours() {
+        let schedule = Schedule::parse(RULES).unwrap();
+        // Wednesday 10:00 CEST
+        assert_eq!(schedule.active(&at("2026-06-17T10:00:00+02:00")), Some(0));
+        // Saturday 10:00 CEST
+        assert_eq!(schedule.active(&at("2026-06-20T10:00:00+02:00")), Some(2));
+    }
+
+    #[test]
+    fn night_spans_midnight() {
+        let schedule = Schedule::parse(RULES).unwrap();
+        assert_eq!(schedule.active(&at("2026-06-17T23:30:00+02:00")), Some(1));
+        assert_eq!(schedule.active(&at("2026-06-18T03:00:00+02:00")), Some(1));
+        assert_eq!(schedule.active(&at("2026-06-18T07:00:00+02:00")), Some(2));
+    }
+
+    #[test]
+    fn sun_times_berlin_midsummer() {
+        let location = Location {
+            lat: 52.52,
+            lon: 13.40,
+        };
+        let date = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        let Sun::RisesAndSets(sunrise, sunset) = sun_times(date, location) else {
+            panic!("the sun rises and sets in Berlin");
+        };
+        // Published times: 02:43 and 19:33 UTC.
+        let expected_rise = date.and_hms_opt(2, 43, 0).unwrap().and_utc();
+        let expected_set = date.and_hms_opt(19, 33, 0).unwrap().and_utc();
+        assert!((sunrise - expected_rise).num_minutes().abs() <= 3);
+        assert!((sunset - expected_set).num_minutes().abs() <= 3);
+    }
+
+    #[test]
+    fn polar_day_and_night_last_all_day() {
+        let tromso = Location {
+            lat: 69.65,
+            lon: 18.96,
+        };
+        let december = NaiveDate::from_ymd_opt(2026, 12, 21).unwrap();
+        let june = NaiveDate::from_ymd_opt(2026, 6, 21).unwrap();
+        assert_eq!(sun_times(december, tromso), Sun::AlwaysDown);
+        assert_eq!(sun_times(june, tromso), Sun::AlwaysUp);
+
+        let schedule =
+            Schedule::parse("location 69.65 18.96\n* sunrise-sunset /day\n* sunset-sunrise /night")
+                .unwrap();
+        for hour in ["00", "12", "23"] {
+            let winter = at(&format!("2026-12-21T{hour}:30:00+01:00"));
+            assert_eq!(schedule.active(&winter), Some(1), "December {hour}:30");
+            let summer = at(&format!("2026-06-21T{hour}:30:00+02:00"));
+            assert_eq!(schedule.active(&summer), Some(0), "June {hour}:30");
+        }
+    }
+
+    #[test]
+    fn transitions_list_rule_changes() {
+        let schedule = Schedule::parse("mon 09:00-10:00 /a\n* * /b").unwrap();
+        let from = at("2026-10-19T08:30:00+00:00");
+        let changes = schedule.transitions(from, from + TimeDelta::hours(2));
+        let summary: Vec<(String, Option<usize>)> = changes
+            .iter()
+            .map(|(t, i)| (t.format("%H:%M").to_string(), *i))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("08:30".into(), Some(1)),
+                ("09:00".into(), Some(0)),
+                ("10:00".into(), Some(1)),
+            ]
+        );
+    }
+}
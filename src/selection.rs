@@ -1,35 +1,45 @@
-pub struct Selection {
-    items: Vec<usize>,
+/// Up to two picked images, one per monitor, identified by `T`.
+pub struct Selection<T> {
+    items: Vec<T>,
 }
 
-impl Selection {
+impl<T: Clone + PartialEq> Selection<T> {
     pub fn new() -> Self {
         Self { items: Vec::new() }
     }
 
-    pub fn click(&mut self, index: usize, shift: bool) {
+    pub fn click(&mut self, item: T, shift: bool) {
         if shift {
-            self.items = vec![index, index];
+            self.items = vec![item.clone(), item];
             return;
         }
         if self.is_duplicated() {
-            if self.items[0] != index {
-                self.items[1] = index;
+            if self.items[0] != item {
+                self.items[1] = item;
             }
-        } else if self.items.contains(&index) {
+        } else if self.items.contains(&item) {
             if self.items.len() == 2 {
                 self.items.swap(0, 1);
             }
         } else if self.items.len() == 2 {
             self.items.remove(1);
-            self.items.push(index);
+            self.items.push(item);
         } else {
-            self.items.push(index);
+            self.items.push(item);
         }
     }
 
-    pub fn badge(&self, image_index: usize) -> Option<&str> {
-        let pos = self.items.iter().position(|&idx| idx == image_index)?;
+    pub fn set(&mut self, items: Vec<T>) {
+        self.items = items;
+    }
+
+    /// Drop the items `keep` rejects.
+    pub fn retain(&mut self, keep: impl FnMut(&T) -> bool) {
+        self.items.retain(keep);
+    }
+
+    pub fn badge(&self, item: &T) -> Option<&str> {
+        let pos = self.items.iter().position(|i| i == item)?;
         if self.is_duplicated() {
             Some("*")
         } else {
@@ -41,7 +51,7 @@ impl Selection {
         }
     }
 
-    pub fn items(&self) -> &[usize] {
+    pub fn items(&self) -> &[T] {
         &self.items
     }
 
@@ -53,10 +63,6 @@ impl Selection {
         self.items.is_empty()
     }
 
-    pub fn clear(&mut self) {
-        self.items.clear();
-    }
-
     fn is_duplicated(&self) -> bool {
         self.items.len() == 2 && self.items[0] == self.items[1]
     }
@@ -66,7 +72,7 @@ impl Selection {
 mod tests {
     use super::*;
 
-    fn sel(items: &[usize]) -> Selection {
+    fn sel(items: &[usize]) -> Selection<usize> {
         Selection { items: items.to_vec() }
     }
 
@@ -168,30 +174,40 @@ mod tests {
     #[test]
     fn badge_empty() {
         let s = sel(&[]);
-        assert_eq!(s.badge(0), None);
+        assert_eq!(s.badge(&0), None);
     }
 
     #[test]
     fn badge_single_selected() {
         let s = sel(&[0]);
-        assert_eq!(s.badge(0), Some("1"));
+        assert_eq!(s.badge(&0), Some("1"));
     }
 
     #[test]
     fn badge_pair_second() {
         let s = sel(&[0, 1]);
-        assert_eq!(s.badge(1), Some("2"));
+        assert_eq!(s.badge(&1), Some("2"));
     }
 
     #[test]
     fn badge_duplicated() {
         let s = sel(&[0, 0]);
-        assert_eq!(s.badge(0), Some("*"));
+        assert_eq!(s.badge(&0), Some("*"));
     }
 
     #[test]
     fn badge_not_selected() {
         let s = sel(&[0, 1]);
-        assert_eq!(s.badge(2), None);
+        assert_eq!(s.badge(&2), None);
+    }
+
+    // --- retain tests ---
+
+    #[test]
+    fn retain_keeps_order_of_remaining() {
+        let mut s = sel(&[3, 1]);
+        s.retain(|&i| i != 3);
+        assert_eq!(s.items(), &[1]);
+        assert_eq!(s.badge(&1), Some("1"));
     }
 }
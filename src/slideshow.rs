@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{history, loader, monitors, random, wallpaper};
+
+/// Where the slideshow takes its images from.
+pub enum Source {
+    Dir(PathBuf),
+    Playlist(PathBuf),
+    Favorites,
+}
+
+impl Source {
+    /// A directory is scanned for images, any other file is read as a playlist.
+    pub fn from_path(path: &Path) -> Self {
+        if path.is_dir() {
+            Self::Dir(path.to_path_buf())
+        } else {
+            Self::Playlist(path.to_path_buf())
+        }
+    }
+
+    /// Collect the current list of images; re-read on every change so edits are picked up.
+    pub fn images(&self) -> Result<Vec<PathBuf>, String> {
+        let images = match self {
+            Self::Dir(dir) => {
+                loader::scan(dir).map_err(|e| format!("failed to read {}: {e}", dir.display()))?
+            }
+            Self::Playlist(file) => read_playlist(file)?,
+            Self::Favorites => read_playlist(&favorites_path())?,
+        };
+        if images.is_empty() {
+            return Err(format!("no images found in {}", self.describe()));
+        }
+        Ok(images)
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Dir(path) | Self::Playlist(path) => path.display().to_string(),
+            Self::Favorites => favorites_path().display().to_string(),
+        }
+    }
+
+    /// Command line arguments that select this source.
+    fn args(&self) -> Result<Vec<String>, String> {
+        match self {
+            Self::Dir(path) | Self::Playlist(path) => {
+                let path = path
+                    .canonicalize()
+                    .map_err(|e| format!("failed to resolve {}: {e}", path.display()))?;
+                Ok(vec![path.to_string_lossy().into_owned()])
+            }
+            Self::Favorites => Ok(vec!["--favorites".into()]),
+        }
+    }
+}
+
+pub struct Options {
+    pub source: Source,
+    pub interval: Duration,
+    pub prefer_fit: bool,
+}
+
+/// Re-pick and apply wallpapers every `interval` until SIGTERM or SIGINT arrives.
+pub fn run(options: &Options) -> Result<(), String> {
+    let stop = stop_flag()?;
+
+    // Fail early on a missing source instead of retrying it forever.
+    options.source.images()?;
+
+    while !stop.load(Ordering::Relaxed) {
+        if let Err(e) = apply_random(&options.source, options.prefer_fit) {
+            eprintln!("error: {e}");
+        }
+        sleep(options.interval, &stop);
+    }
+
+    eprintln!("Slideshow stopped.");
+    Ok(())
+}
+
+/// A flag that gets set once SIGTERM or SIGINT arrives.
+pub fn stop_flag() -> Result<Arc<AtomicBool>, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGINT] {
+        signal_hook::flag::register(signal, Arc::clone(&stop))
+            .map_err(|e| format!("failed to install signal handler: {e}"))?;
+    }
+    Ok(stop)
+}
+
+/// Pick random images from `source` for the current monitors and apply them.
+pub fn apply_random(source: &Source, prefer_fit: bool) -> Result<(), String> {
+    let images = source.images()?;
+    let monitors = monitors::detect()?;
+    let recent = history::recent();
+    let assignments = random::pick_from_paths(&images, &monitors, &recent, prefer_fit);
+    for a in &assignments {
+        eprintln!("{}: {}", a.monitor.name, a.path.display());
+    }
+    wallpaper::apply(&assignments, &|msg| eprintln!("{msg}"))
+}
+
+/// Sleep for `duration`, waking up early once `stop` is set.
+pub fn sleep(duration: Duration, stop: &AtomicBool) {
+    let start = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        let Some(left) = duration.checked_sub(start.elapsed()) else {
+            return;
+        };
+        std::thread::sleep(left.min(Duration::from_millis(250)));
+    }
+}
+
+/// Render a systemd user unit that runs the slideshow with the same options.
+pub fn systemd_unit(options: &Options) -> Result<String, String> {
+    let exe =
+        std::env::current_exe().map_err(|e| format!("failed to locate the executable: {e}"))?;
+    let mut args = vec![exe.to_string_lossy().into_owned(), "slideshow".into()];
+    args.extend(options.source.args()?);
+    args.push("--interval".into());
+    args.push((options.interval.as_secs() / 60).to_string());
+    if options.prefer_fit {
+        args.push("--fit".into());
+    }
+    let exec = args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ");
+
+    Ok(format!(
+        "[Unit]
+Description=nitrohydra wallpaper slideshow
+PartOf=graphical-session.target
+After=graphical-session.target
+
+[Service]
+ExecStart={exec}
+Restart=on-failure
+
+[Install]
+WantedBy=graphical-session.target
+"
+    ))
+}
+
+/// Quote an argument for systemd's `ExecStart=` when it contains whitespace,
+/// quotes, or the `%` and `$` that systemd would expand (escaped by doubling).
+fn quote(arg: &str) -> String {
+    if arg.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\\' | '%' | '$')) {
+        let escaped = arg
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$");
+        format!("\"{escaped}\"")
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Read a playlist: one image path per line, `#` starts a comment.
+/// Relative paths are resolved against the playlist's directory.
+fn read_playlist(file: &Path) -> Result<Vec<PathBuf>, String> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| format!("failed to read {}: {e}", file.display()))?;
+    let base = file.parent().unwrap_or(Path::new(""));
+    Ok(parse_playlist(&contents, base))
+}
+
+fn parse_playlist(contents: &str, base: &Path) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| base.join(line))
+        .collect()
+}
+
+fn favorites_path() -> PathBuf {
+    wallpaper::dirs_config().join("nitrohydra/favorites")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_skips_comments_and_blank_lines() {
+        let contents = "# mine\n\n/abs/a.jpg\n  b.png  \n";
+        assert_eq!(
+            parse_playlist(contents, Path::new("/lists")),
+            vec![PathBuf::from("/abs/a.jpg"), PathBuf::from("/lists/b.png")]
+        );
+    }
+
+    #[test]
+    fn quote_plain_argument() {
+        assert_eq!(quote("/usr/bin/nitrohydra"), "/usr/bin/nitrohydra");
+    }
+
+    #[test]
+    fn quote_argument_with_spaces() {
+        assert_eq!(quote("/home/me/My Pictures"), "\"/home/me/My Pictures\"");
+    }
+
+    #[test]
+    fn quote_argument_with_specifiers() {
+        assert_eq!(quote("/home/me/100%"), "\"/home/me/100%%\"");
+        assert_eq!(quote("/w/$HOME"), "\"/w/$$HOME\"");
+    }
+}
@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gallery::ImageEntry;
+use crate::monitors::Monitor;
+use crate::random;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKey {
+    /// File name, with numbers compared by value.
+    Name,
+    Modified,
+    Size,
+    /// Pixel count.
+    Resolution,
+    Aspect,
+    /// How far the aspect ratio is from the closest monitor's; best fit first.
+    Fit,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 6] = [
+        SortKey::Name,
+        SortKey::Modified,
+        SortKey::Size,
+        SortKey::Resolution,
+        SortKey::Aspect,
+        SortKey::Fit,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Modified => "Modified",
+            SortKey::Size => "File size",
+            SortKey::Resolution => "Resolution",
+            SortKey::Aspect => "Aspect ratio",
+            SortKey::Fit => "Fit to monitor",
+        }
+    }
+}
+
+/// The gallery order chosen by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortOrder {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Modified,
+            descending: true,
+        }
+    }
+}
+
+/// Compares gallery entries by a `SortOrder`, with the monitors to fit for `SortKey::Fit`.
+pub struct Sorter {
+    pub order: SortOrder,
+    pub monitors: Vec<Monitor>,
+}
+
+impl Sorter {
+    pub fn compare(&self, a: &ImageEntry, b: &ImageEntry) -> Ordering {
+        let ordering = match self.order.key {
+            SortKey::Name => natural_cmp(&file_name(a), &file_name(b)),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Size => a.file_size.cmp(&b.file_size),
+            SortKey::Resolution => pixels(a).cmp(&pixels(b)),
+            SortKey::Aspect => aspect(a).total_cmp(&aspect(b)),
+            SortKey::Fit => self.fit(a).total_cmp(&self.fit(b)),
+        };
+        let ordering = if self.order.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        // Equal keys still need a stable place, or entries would jump around.
+        ordering.then_with(|| a.path.cmp(&b.path))
+    }
+
+    /// Index at which `entry` keeps the sorted `entries` sorted.
+    pub fn position(&self, entries: &[ImageEntry], entry: &ImageEntry) -> usize {
+        entries.partition_point(|e| self.compare(e, entry) == Ordering::Less)
+    }
+
+    fn fit(&self, entry: &ImageEntry) -> f64 {
+        self.monitors
+            .iter()
+            .map(|m| random::aspect_distance(entry.original_size, m))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+fn file_name(entry: &ImageEntry) -> String {
+    entry
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn pixels(entry: &ImageEntry) -> u64 {
+    let [w, h] = entry.original_size;
+    u64::from(w) * u64::from(h)
+}
+
+fn aspect(entry: &ImageEntry) -> f64 {
+    let [w, h] = entry.original_size;
+    f64::from(w) / f64::from(h.max(1))
+}
+
+/// Compare strings with runs of digits ordered by their value, so `img2` comes before `img10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(ca), Some(cb)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+        if ca.is_ascii_digit() && cb.is_ascii_digit() {
+            let (na, rest_a) = split_digits(a);
+            let (nb, rest_b) = split_digits(b);
+            let value = |n: &str| n.trim_start_matches('0').to_string();
+            let (va, vb) = (value(na), value(nb));
+            let ordering = va.len().cmp(&vb.len()).then_with(|| va.cmp(&vb));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (rest_a, rest_b);
+        } else {
+            if ca != cb {
+                return ca.cmp(&cb);
+            }
+            (a, b) = (&a[ca.len_utf8()..], &b[cb.len_utf8()..]);
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let mut names = vec!["img10.jpg", "img2.jpg", "img1.jpg", "img002b.jpg", "a.jpg"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            ["a.jpg", "img1.jpg", "img2.jpg", "img002b.jpg", "img10.jpg"]
+        );
+    }
+
+    #[test]
+    fn natural_order_of_prefixes() {
+        assert_eq!(natural_cmp("img", "img1"), Ordering::Less);
+        assert_eq!(natural_cmp("b", "a10"), Ordering::Greater);
+        assert_eq!(natural_cmp("x7", "x7"), Ordering::Equal);
+    }
+}
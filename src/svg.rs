@@ -0,0 +1,185 @@
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{ImageError, RgbImage, RgbaImage};
+use resvg::{tiny_skia, usvg};
+
+use crate::wallpaper::{Fit, Framing};
+
+/// Whether `path` names an SVG document, plain or gzipped.
+pub fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg") || ext.eq_ignore_ascii_case("svgz"))
+}
+
+/// Parse the SVG document at `path`. Relative links to embedded images are
+/// resolved next to it.
+pub fn load(path: &Path) -> Result<usvg::Tree, ImageError> {
+    let data = std::fs::read(path)?;
+    let options = usvg::Options {
+        resources_dir: path.parent().map(Path::to_path_buf),
+        fontdb: fonts(),
+        ..Default::default()
+    };
+    usvg::Tree::from_data(&data, &options).map_err(decoding_error)
+}
+
+/// The system fonts for text in SVGs, looked up once.
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut db = usvg::fontdb::Database::new();
+            db.load_system_fonts();
+            Arc::new(db)
+        })
+        .clone()
+}
+
+/// Intrinsic size of the document in pixels, at least 1×1.
+pub fn size(tree: &usvg::Tree) -> (u32, u32) {
+    let size = tree.size();
+    (
+        (size.width().ceil() as u32).max(1),
+        (size.height().ceil() as u32).max(1),
+    )
+}
+
+/// Rasterize the document scaled by `scale`, keeping its transparency.
+pub fn render(tree: &usvg::Tree, scale: f32) -> Result<RgbaImage, ImageError> {
+    let size = tree.size();
+    let width = ((size.width() * scale).round() as u32).max(1);
+    let height = ((size.height() * scale).round() as u32).max(1);
+    let pixmap = rasterize(
+        tree,
+        width,
+        height,
+        tiny_skia::Transform::from_scale(scale, scale),
+        tiny_skia::Color::TRANSPARENT,
+    )?;
+    Ok(to_rgba(&pixmap))
+}
+
+/// Rasterize the document straight into a `target_w`×`target_h` tile fitted
+/// as `framing` says, on black like `wallpaper::fit_resize`.
+pub fn render_fit(
+    tree: &usvg::Tree,
+    target_w: u32,
+    target_h: u32,
+    framing: Framing,
+) -> Result<RgbImage, ImageError> {
+    let pixmap = rasterize(
+        tree,
+        target_w,
+        target_h,
+        fit_transform(tree.size(), target_w, target_h, framing),
+        tiny_skia::Color::BLACK,
+    )?;
+    Ok(image::DynamicImage::from(to_rgba(&pixmap)).to_rgb8())
+}
+
+/// Scale and offset that place a document of `size` into the target.
+fn fit_transform(
+    size: usvg::Size,
+    target_w: u32,
+    target_h: u32,
+    framing: Framing,
+) -> tiny_skia::Transform {
+    let (target_w, target_h) = (target_w as f32, target_h as f32);
+    let scale_w = target_w / size.width();
+    let scale_h = target_h / size.height();
+
+    match framing.fit {
+        Fit::Cover => {
+            let scale = scale_w.max(scale_h);
+            let [cx, cy] = framing.crop.map(|c| c.clamp(0.0, 1.0));
+            let x = (size.width() * scale - target_w) * cx;
+            let y = (size.height() * scale - target_h) * cy;
+            tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, -x, -y)
+        }
+        Fit::Contain => {
+            let scale = scale_w.min(scale_h);
+            let x = (target_w - size.width() * scale) / 2.0;
+            let y = (target_h - size.height() * scale) / 2.0;
+            tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, x, y)
+        }
+        Fit::Stretch => tiny_skia::Transform::from_scale(scale_w, scale_h),
+    }
+}
+
+fn rasterize(
+    tree: &usvg::Tree,
+    width: u32,
+    height: u32,
+    transform: tiny_skia::Transform,
+    background: tiny_skia::Color,
+) -> Result<tiny_skia::Pixmap, ImageError> {
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| decoding_error(format!("cannot render at {width}×{height}")))?;
+    pixmap.fill(background);
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+fn to_rgba(pixmap: &tiny_skia::Pixmap) -> RgbaImage {
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(pixmap.width(), pixmap.height(), rgba).expect("buffer matches the pixmap")
+}
+
+fn decoding_error(e: impl std::fmt::Display) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("SVG".into()),
+        e.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 200×100: red left half, blue right half.
+    fn halves() -> usvg::Tree {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100">
+            <rect width="100" height="100" fill="red"/>
+            <rect x="100" width="100" height="100" fill="blue"/>
+        </svg>"#;
+        usvg::Tree::from_str(svg, &usvg::Options::default()).unwrap()
+    }
+
+    #[test]
+    fn renders_each_fit_at_tile_size() {
+        let tree = halves();
+        assert_eq!(size(&tree), (200, 100));
+
+        let cover = |crop| Framing {
+            fit: Fit::Cover,
+            crop,
+        };
+        let left = render_fit(&tree, 300, 300, cover([0.0, 0.5])).unwrap();
+        assert_eq!(left.dimensions(), (300, 300));
+        assert_eq!(left.get_pixel(299, 150).0, [255, 0, 0]);
+        let right = render_fit(&tree, 300, 300, cover([1.0, 0.5])).unwrap();
+        assert_eq!(right.get_pixel(0, 150).0, [0, 0, 255]);
+
+        let contain = Framing {
+            fit: Fit::Contain,
+            ..Framing::default()
+        };
+        let tile = render_fit(&tree, 400, 400, contain).unwrap();
+        assert_eq!(tile.get_pixel(10, 10).0, [0, 0, 0]);
+        assert_eq!(tile.get_pixel(10, 200).0, [255, 0, 0]);
+        assert_eq!(tile.get_pixel(390, 200).0, [0, 0, 255]);
+
+        let thumbnail = render(&tree, 0.5).unwrap();
+        assert_eq!(thumbnail.dimensions(), (100, 50));
+    }
+}
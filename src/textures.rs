@@ -0,0 +1,194 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use eframe::egui;
+
+/// Video memory the gallery thumbnails may take, in bytes.
+const BUDGET: usize = 256 * 1024 * 1024;
+
+/// Uploaded gallery thumbnails, kept within `BUDGET` by dropping the ones
+/// shown least recently.
+pub struct Textures {
+    slots: HashMap<PathBuf, Slot>,
+    bytes: usize,
+    budget: usize,
+    /// Counts `next_frame` calls; a slot remembers the last frame it was drawn in.
+    frame: u64,
+}
+
+struct Slot {
+    texture: egui::TextureHandle,
+    bytes: usize,
+    /// 0 for thumbnails neither drawn nor wanted yet.
+    last_used: Cell<u64>,
+}
+
+impl Textures {
+    pub fn new() -> Self {
+        Self::with_budget(BUDGET)
+    }
+
+    fn with_budget(budget: usize) -> Self {
+        Self {
+            slots: HashMap::new(),
+            bytes: 0,
+            budget,
+            frame: 1,
+        }
+    }
+
+    /// The thumbnail of `path`, marked as drawn in this frame.
+    pub fn get(&self, path: &Path) -> Option<&egui::TextureHandle> {
+        let slot = self.slots.get(path)?;
+        slot.last_used.set(self.frame);
+        Some(&slot.texture)
+    }
+
+    pub fn insert(&mut self, ctx: &egui::Context, path: &Path, image: egui::ColorImage) {
+        let bytes = image.width() * image.height() * 4;
+        if let Some(slot) = self.slots.get_mut(path) {
+            slot.texture.set(image, Default::default());
+            self.bytes = self.bytes - slot.bytes + bytes;
+            slot.bytes = bytes;
+            return;
+        }
+        let name = path.to_string_lossy();
+        let slot = Slot {
+            texture: ctx.load_texture(name, image, Default::default()),
+            bytes,
+            last_used: Cell::new(0),
+        };
+        self.slots.insert(path.to_path_buf(), slot);
+        self.bytes += bytes;
+    }
+
+    /// Whether the thumbnail of `path` is uploaded. Marks it as wanted in this
+    /// frame, so thumbnails decoded ahead of the view stay until they are drawn.
+    pub fn keep(&self, path: &Path) -> bool {
+        let Some(slot) = self.slots.get(path) else {
+            return false;
+        };
+        slot.last_used.set(self.frame);
+        true
+    }
+
+    /// Whether another thumbnail would exceed the budget.
+    pub fn is_full(&self) -> bool {
+        self.bytes >= self.budget
+    }
+
+    /// Drop the thumbnails of `path` and everything below it.
+    pub fn remove(&mut self, path: &Path) {
+        self.slots.retain(|p, slot| {
+            let keep = !p.starts_with(path);
+            if !keep {
+                self.bytes -= slot.bytes;
+            }
+            keep
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.bytes = 0;
+    }
+
+    /// Start a new frame, first unloading the least recently drawn thumbnails
+    /// while over budget. The ones drawn in the frame just finished stay.
+    pub fn next_frame(&mut self) {
+        if self.bytes > self.budget {
+            let mut candidates: Vec<(u64, PathBuf)> = self
+                .slots
+                .iter()
+                .filter(|(_, slot)| slot.last_used.get() < self.frame)
+                .map(|(path, slot)| (slot.last_used.get(), path.clone()))
+                .collect();
+            candidates.sort();
+            for (_, path) in candidates {
+                if self.bytes <= self.budget {
+                    break;
+                }
+                if let Some(slot) = self.slots.remove(&path) {
+                    self.bytes -= slot.bytes;
+                }
+            }
+        }
+        self.frame += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> egui::ColorImage {
+        egui::ColorImage::new([16, 16], egui::Color32::BLACK)
+    }
+
+    #[test]
+    fn evicts_least_recently_drawn() {
+        let ctx = egui::Context::default();
+        let mut textures = Textures::with_budget(3 * 16 * 16 * 4);
+        for name in ["a", "b", "c", "d"] {
+            textures.insert(&ctx, Path::new(name), image());
+        }
+        for name in ["a", "c"] {
+            textures.get(Path::new(name));
+        }
+        textures.next_frame();
+        textures.get(Path::new("d"));
+        textures.next_frame();
+
+        // b was never drawn, so it goes; the rest fit the budget again.
+        assert!(!textures.slots.contains_key(Path::new("b")));
+        assert_eq!(textures.slots.len(), 3);
+        assert_eq!(textures.bytes, textures.budget);
+    }
+
+    #[test]
+    fn keeps_thumbnails_in_view_over_budget() {
+        let ctx = egui::Context::default();
+        let mut textures = Textures::with_budget(16 * 16 * 4);
+        for name in ["a", "b"] {
+            textures.insert(&ctx, Path::new(name), image());
+            textures.get(Path::new(name));
+        }
+        textures.next_frame();
+        assert_eq!(textures.slots.len(), 2);
+
+        textures.remove(Path::new("a"));
+        assert_eq!(textures.bytes, 16 * 16 * 4);
+    }
+
+    #[test]
+    fn keeps_thumbnails_ahead_of_view_over_budget() {
+        let ctx = egui::Context::default();
+        let mut textures = Textures::with_budget(2 * 16 * 16 * 4);
+        let in_view = [Path::new("a"), Path::new("b")];
+        let ahead = [Path::new("c")];
+        for frame in 0..3 {
+            // Like `Gallery::poll`, then the grid: the requested thumbnails
+            // arrive, the missing ones are looked up, the ones in view drawn.
+            textures.next_frame();
+            if frame == 0 {
+                for path in in_view.iter().chain(&ahead) {
+                    textures.insert(&ctx, path, image());
+                }
+            }
+            for path in in_view.iter().chain(&ahead) {
+                assert!(textures.keep(path), "{path:?} unloaded in frame {frame}");
+            }
+            for path in in_view {
+                textures.get(path);
+            }
+        }
+
+        // Scrolled on: the thumbnail that left the lookahead goes.
+        textures.next_frame();
+        textures.keep(Path::new("c"));
+        textures.get(Path::new("c"));
+        textures.next_frame();
+        assert_eq!(textures.slots.len(), 2);
+    }
+}
@@ -1,35 +1,201 @@
+use std::collections::BTreeSet;
 use std::path::{Path, PathBuf};
 
 use eframe::egui;
 
-use crate::gallery::{self, ImageEntry};
+use crate::filter::{self, Filter, Orientation};
+use crate::folders::Folder;
+use crate::gallery::{self, ImageEntry, Root};
 use crate::logic::App;
-use crate::monitors::Monitor;
+use crate::overrides;
+use crate::sort::SortKey;
+use crate::wallpaper::{Assignment, Fit, Framing};
+
+/// Rows below the view whose thumbnails are decoded right after the visible ones.
+const LOOKAHEAD_ROWS: usize = 2;
+
+enum SelectionAction {
+    Apply(Vec<Assignment>),
+    Shuffle,
+    Frame(usize, Framing),
+}
+
+enum ProfileAction {
+    Apply(usize),
+    StartRename(usize),
+    Rename(usize, String),
+    Delete(usize),
+    Save,
+    Restore,
+}
 
 impl App {
-    pub(crate) fn show_path_input(&mut self, ui: &mut egui::Ui) {
+    pub(crate) fn show_roots(&mut self, ui: &mut egui::Ui) {
+        let mut reload = false;
+        let mut remove = None;
+
         ui.add_space(3.0);
-        ui.label("Directory path:");
+        ui.label("Library folders:");
         ui.add_space(3.0);
+        let mut loaded = 0;
+        for (i, root) in self.roots.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                reload |= ui
+                    .checkbox(&mut root.enabled, "")
+                    .on_hover_text("Include this folder in the gallery")
+                    .changed();
+                // The same color marks thumbnails from this folder.
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                if root.enabled && !root.path.is_empty() {
+                    ui.painter()
+                        .circle_filled(rect.center(), 5.0, source_color(loaded));
+                    loaded += 1;
+                }
+                if ui
+                    .button("✕")
+                    .on_hover_text("Remove from the library")
+                    .clicked()
+                {
+                    remove = Some(i);
+                }
+                ui.add(
+                    egui::TextEdit::singleline(&mut root.path)
+                        .desired_width(f32::INFINITY)
+                        .margin(egui::Margin::symmetric(7.0, 5.0)),
+                );
+            });
+        }
         ui.horizontal(|ui| {
-            if ui.button("Open").clicked()
+            if ui.button("Add folder").clicked()
                 && let Some(dir) = rfd::FileDialog::new()
-                    .set_directory(&self.path)
+                    .set_directory(self.roots.last().map_or("", |r| r.path.as_str()))
                     .pick_folder()
             {
-                self.path = dir.to_string_lossy().into_owned();
-                self.load_images(ui.ctx());
+                self.roots.push(Root {
+                    path: dir.to_string_lossy().into_owned(),
+                    enabled: true,
+                });
+                reload = true;
             }
             if ui.button("Reload").clicked() {
-                self.load_images(ui.ctx());
+                reload = true;
             }
+        });
+        ui.add_space(3.0);
+
+        if let Some(i) = remove {
+            self.roots.remove(i);
+            reload = true;
+        }
+        if reload {
+            self.load_images(ui.ctx());
+        }
+    }
+
+    pub(crate) fn show_profiles(&mut self, ui: &mut egui::Ui) {
+        let mut action = None;
+        let can_save = self.assignments().is_some();
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Profiles:");
+            for (i, profile) in self.profiles.iter().enumerate() {
+                if let Some((index, name)) = &mut self.renaming
+                    && *index == i
+                {
+                    let response = ui.add(egui::TextEdit::singleline(name).desired_width(120.0));
+                    if response.lost_focus() {
+                        action = Some(ProfileAction::Rename(i, name.clone()));
+                    } else {
+                        response.request_focus();
+                    }
+                    continue;
+                }
+
+                let response = ui
+                    .add_enabled(!self.apply.is_running(), egui::Button::new(&profile.name))
+                    .on_hover_text("Click to apply, right-click to rename or delete");
+                if response.clicked() {
+                    action = Some(ProfileAction::Apply(i));
+                }
+                response.context_menu(|ui| {
+                    if ui.button("Rename").clicked() {
+                        action = Some(ProfileAction::StartRename(i));
+                        ui.close_menu();
+                    }
+                    if ui.button("Delete").clicked() {
+                        action = Some(ProfileAction::Delete(i));
+                        ui.close_menu();
+                    }
+                });
+            }
+
             ui.add(
-                egui::TextEdit::singleline(&mut self.path)
-                    .desired_width(f32::INFINITY)
-                    .margin(egui::Margin::symmetric(7.0, 5.0)),
+                egui::TextEdit::singleline(&mut self.profile_name)
+                    .hint_text("Profile name")
+                    .desired_width(120.0),
             );
+            let name_ok = !self.profile_name.trim().is_empty();
+            if ui
+                .add_enabled(can_save && name_ok, egui::Button::new("Save"))
+                .on_hover_text("Save the current selection as a profile")
+                .clicked()
+            {
+                action = Some(ProfileAction::Save);
+            }
+
+            if ui
+                .add_enabled(!self.apply.is_running(), egui::Button::new("Restore"))
+                .on_hover_text("Re-apply the images last used with these monitors")
+                .clicked()
+            {
+                action = Some(ProfileAction::Restore);
+            }
+
+            if let Some(e) = &self.profiles_error {
+                ui.colored_label(egui::Color32::RED, e);
+            }
         });
         ui.add_space(3.0);
+
+        match action {
+            Some(ProfileAction::Apply(i)) => self.apply_profile(i, ui.ctx()),
+            Some(ProfileAction::StartRename(i)) => {
+                self.renaming = Some((i, self.profiles[i].name.clone()));
+            }
+            Some(ProfileAction::Rename(i, name)) => {
+                self.renaming = None;
+                self.rename_profile(i, name);
+            }
+            Some(ProfileAction::Delete(i)) => self.delete_profile(i),
+            Some(ProfileAction::Save) => self.save_profile(),
+            Some(ProfileAction::Restore) => self.restore_layout(ui.ctx()),
+            None => {}
+        }
+    }
+
+    fn show_sort(&mut self, ui: &mut egui::Ui) {
+        let mut order = self.gallery.sort_order();
+        ui.label("Sort");
+        egui::ComboBox::from_id_salt("sort_key")
+            .selected_text(order.key.label())
+            .show_ui(ui, |ui| {
+                for key in SortKey::ALL {
+                    ui.selectable_value(&mut order.key, key, key.label());
+                }
+            });
+        let arrow = if order.descending { "⬇" } else { "⬆" };
+        let hover = if order.descending {
+            "Descending"
+        } else {
+            "Ascending"
+        };
+        if ui.button(arrow).on_hover_text(hover).clicked() {
+            order.descending = !order.descending;
+        }
+        if order != self.gallery.sort_order() {
+            self.gallery.set_sort_order(order);
+        }
     }
 
     pub(crate) fn show_size_slider(&mut self, ui: &mut egui::Ui) {
@@ -37,6 +203,7 @@ impl App {
         ui.horizontal(|ui| {
             ui.label("Size");
             ui.add(egui::Slider::new(&mut self.thumb_size, 50.0..=400.0));
+            self.show_sort(ui);
             if self.gallery.is_loading() {
                 ui.spinner();
             }
@@ -45,54 +212,123 @@ impl App {
                 Ok(monitors) if !monitors.is_empty() => monitors
                     .iter()
                     .enumerate()
-                    .map(|(i, m)| format!("#{} {} — {}×{}", i + 1, m.name, m.width, m.height))
+                    .map(|(i, m)| {
+                        // Mirrored outputs share a slot: "#1 DP-4 = HDMI-0 — 1920×1080".
+                        let names = std::iter::once(&m.name)
+                            .chain(&m.mirrors)
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join(" = ");
+                        let scale = if m.scale == 1.0 {
+                            String::new()
+                        } else {
+                            format!(" @{}x", (m.scale * 100.0).round() / 100.0)
+                        };
+                        format!("#{} {names} — {}×{}{scale}", i + 1, m.width, m.height)
+                    })
                     .collect::<Vec<_>>()
                     .join(", "),
                 Ok(_) => "No monitors detected".into(),
                 Err(e) => e.clone(),
             };
+            let displays = match &self.monitors {
+                Ok(monitors) => monitors
+                    .iter()
+                    .filter_map(|m| {
+                        let edid = m.edid.as_ref()?;
+                        Some(format!(
+                            "{}: {} {} {}",
+                            m.name, edid.manufacturer, edid.model, edid.serial
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                Err(_) => String::new(),
+            };
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                ui.weak(&text);
+                let label = ui.weak(&text);
+                if !displays.is_empty() {
+                    label.on_hover_text(displays);
+                }
+                if self.monitor_overrides {
+                    ui.colored_label(ui.visuals().warn_fg_color, "Overridden")
+                        .on_hover_text(format!(
+                            "Monitors adjusted by {}",
+                            overrides::path().display()
+                        ));
+                }
             });
         });
         ui.add_space(3.0);
     }
 
     pub(crate) fn show_selection(&mut self, ui: &mut egui::Ui) {
-        let Some(entries) = self.gallery.entries() else {
-            return;
-        };
-        if self.selected.is_empty() {
-            return;
-        }
-
-        if let Some(assignments) = self.show_selection_row(ui, entries) {
-            self.apply.start(assignments, ui.ctx());
+        match self.show_selection_row(ui) {
+            Some(SelectionAction::Apply(assignments)) => self.apply.start(assignments, ui.ctx()),
+            Some(SelectionAction::Shuffle) => self.shuffle(),
+            Some(SelectionAction::Frame(slot, framing)) => self.framing[slot] = framing,
+            None => {}
         }
     }
 
-    fn show_selection_row(
-        &self,
-        ui: &mut egui::Ui,
-        entries: &[ImageEntry],
-    ) -> Option<Vec<(PathBuf, Monitor)>> {
+    fn show_selection_row(&self, ui: &mut egui::Ui) -> Option<SelectionAction> {
         let mut action = None;
-        let can_act = matches!(&self.monitors, Ok(m) if m.len() >= 2) && self.selected.len() == 2;
         let busy = self.apply.is_running();
 
         ui.horizontal(|ui| {
-            for (slot, &idx) in self.selected.items().iter().enumerate() {
-                let entry = &entries[idx];
+            for (slot, path) in self.selected.items().iter().enumerate() {
+                let mut framing = self.framing[slot];
                 ui.vertical(|ui| {
                     ui.label(format!("#{}", slot + 1));
-                    ui.add(
-                        egui::Image::new(&entry.texture)
+                    // While the gallery reloads, the image or its thumbnail may not be back yet.
+                    let Some(texture) = self.gallery.texture(path) else {
+                        let name = path.file_name().unwrap_or_default().to_string_lossy();
+                        ui.add_sized([120.0, 120.0], egui::Label::new(name).wrap());
+                        return;
+                    };
+                    let response = ui.add(
+                        egui::Image::new(texture)
                             .maintain_aspect_ratio(true)
-                            .fit_to_exact_size(egui::vec2(120.0, 120.0)),
+                            .fit_to_exact_size(egui::vec2(120.0, 120.0))
+                            .sense(egui::Sense::click()),
                     );
+                    if framing.fit == Fit::Cover {
+                        paint_crop_marker(ui, response.rect, framing.crop);
+                        if response.clicked()
+                            && let Some(pos) = response.interact_pointer_pos()
+                        {
+                            let rel = (pos - response.rect.min) / response.rect.size();
+                            framing.crop = [rel.x.clamp(0.0, 1.0), rel.y.clamp(0.0, 1.0)];
+                        }
+                        response.on_hover_text("Click to choose which part is kept");
+                    }
+                    egui::ComboBox::from_id_salt(("fit", slot))
+                        .width(120.0)
+                        .selected_text(framing.fit.label())
+                        .show_ui(ui, |ui| {
+                            for fit in Fit::ALL {
+                                ui.selectable_value(&mut framing.fit, fit, fit.label());
+                            }
+                        });
                 });
+                if framing != self.framing[slot] {
+                    action = Some(SelectionAction::Frame(slot, framing));
+                }
             }
 
+            ui.vertical(|ui| {
+                let label_height =
+                    ui.text_style_height(&egui::TextStyle::Body) + ui.spacing().item_spacing.y;
+                ui.add_space(label_height);
+                if ui
+                    .add_enabled(!busy, egui::Button::new("Shuffle"))
+                    .on_hover_text("Pick random images, avoiding recently used ones")
+                    .clicked()
+                {
+                    action = Some(SelectionAction::Shuffle);
+                }
+            });
+
             if self.preview.has_texture() || self.preview.is_running() {
                 ui.add_space(8.0);
                 ui.separator();
@@ -119,22 +355,10 @@ impl App {
                         if !log.is_empty() {
                             ui.weak(log);
                         }
-                    } else if can_act {
-                        let monitors = self.monitors.as_ref().unwrap();
-                        let assignments = || {
-                            self.selected
-                                .items()
-                                .iter()
-                                .zip(monitors.iter())
-                                .map(|(&idx, monitor)| {
-                                    let path = PathBuf::from(entries[idx].texture.name());
-                                    (path, monitor.clone())
-                                })
-                                .collect()
-                        };
-                        if ui.button("Apply").clicked() {
-                            action = Some(assignments());
-                        }
+                    } else if let Some(assignments) = self.assignments()
+                        && ui.button("Apply").clicked()
+                    {
+                        action = Some(SelectionAction::Apply(assignments));
                     }
 
                     if let Some(status) = self.apply.status() {
@@ -154,65 +378,271 @@ impl App {
         action
     }
 
+    pub(crate) fn show_folders(&mut self, ui: &mut egui::Ui) {
+        let folders = self.gallery.folders();
+        let mut chosen = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            if let [root] = folders {
+                let current = self.folder.as_deref().unwrap_or(&root.path);
+                show_folder(ui, root, current, &mut chosen, true);
+                return;
+            }
+            let total: usize = folders.iter().map(|f| f.count).sum();
+            if ui
+                .selectable_label(self.folder.is_none(), format!("All ({total})"))
+                .clicked()
+            {
+                chosen = Some(PathBuf::new());
+            }
+            let current = self.folder.as_deref().unwrap_or(Path::new(""));
+            for root in folders {
+                show_folder(ui, root, current, &mut chosen, false);
+            }
+        });
+        if let Some(path) = chosen {
+            // "All" and the only root both show everything.
+            let everything =
+                path.as_os_str().is_empty() || matches!(folders, [root] if root.path == path);
+            self.folder = (!everything).then_some(path);
+        }
+    }
+
     pub(crate) fn show_gallery(&mut self, ui: &mut egui::Ui) {
-        let loading = self.gallery.is_loading();
+        let scanning = self.gallery.is_scanning();
+        if self.has_images() {
+            self.show_filter_bar(ui);
+        }
+        self.show_failures(ui);
 
         match self.gallery.state() {
             gallery::State::Empty => {}
             gallery::State::Error(e) => {
                 ui.colored_label(egui::Color32::RED, e);
             }
-            gallery::State::Loaded(entries) if entries.is_empty() && !loading => {
+            gallery::State::Loaded(entries) if entries.is_empty() && !scanning => {
                 ui.label("No images found.");
             }
             gallery::State::Loaded(entries) if entries.is_empty() => {}
             gallery::State::Loaded(entries) => {
-                let clicked = self.show_image_grid(ui, entries);
-                if let Some((i, shift)) = clicked
-                    && !loading
-                {
-                    self.handle_image_click(i, shift);
+                if let Some((path, shift)) = self.show_image_grid(ui, entries) {
+                    self.handle_image_click(path, shift);
                 }
             }
         }
     }
 
-    fn show_image_grid(&self, ui: &mut egui::Ui, entries: &[ImageEntry]) -> Option<(usize, bool)> {
+    /// Files that could not be read, folded away under a count.
+    fn show_failures(&self, ui: &mut egui::Ui) {
+        let failures = self.gallery.failures();
+        if failures.is_empty() {
+            return;
+        }
+        let title = match failures.len() {
+            1 => "⚠ 1 file failed to load".to_string(),
+            n => format!("⚠ {n} files failed to load"),
+        };
+        egui::CollapsingHeader::new(egui::RichText::new(title).color(ui.visuals().warn_fg_color))
+            .id_salt("failures")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("failures_list")
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        for (path, error) in failures {
+                            ui.label(format!("{}: {error}", path.display()));
+                        }
+                    });
+            });
+    }
+
+    fn show_filter_bar(&mut self, ui: &mut egui::Ui) {
+        let entries = self.gallery.entries().unwrap_or_default();
+        let monitors = self.monitors.as_deref().unwrap_or_default();
+        let filter = &mut self.filter;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut filter.query)
+                    .hint_text("Search, e.g. snow or *.png")
+                    .desired_width(160.0),
+            );
+
+            ui.label("Min size");
+            ui.add(egui::DragValue::new(&mut filter.min_width).speed(10));
+            ui.label("×");
+            ui.add(egui::DragValue::new(&mut filter.min_height).speed(10));
+
+            egui::ComboBox::from_id_salt("filter_orientation")
+                .selected_text(filter.orientation.map_or("Any shape", |o| o.label()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.orientation, None, "Any shape");
+                    for orientation in Orientation::ALL {
+                        ui.selectable_value(
+                            &mut filter.orientation,
+                            Some(orientation),
+                            orientation.label(),
+                        );
+                    }
+                });
+
+            let covers = |i: usize| format!("Covers #{} {}", i + 1, monitors[i].name);
+            egui::ComboBox::from_id_salt("filter_monitor")
+                .selected_text(
+                    filter
+                        .monitor
+                        .filter(|&i| i < monitors.len())
+                        .map_or("Any size".into(), covers),
+                )
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.monitor, None, "Any size");
+                    for i in 0..monitors.len() {
+                        ui.selectable_value(&mut filter.monitor, Some(i), covers(i));
+                    }
+                });
+
+            egui::ComboBox::from_id_salt("filter_type")
+                .selected_text(filter.file_type.as_deref().unwrap_or("All types"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut filter.file_type, None, "All types");
+                    let types: BTreeSet<String> = entries
+                        .iter()
+                        .filter_map(|e| filter::file_type_of(&e.path))
+                        .collect();
+                    for file_type in types {
+                        let label = file_type.clone();
+                        ui.selectable_value(&mut filter.file_type, Some(file_type), label);
+                    }
+                });
+
+            if filter.is_active() {
+                if ui.button("Clear").clicked() {
+                    *filter = Filter::default();
+                }
+                let shown = entries
+                    .iter()
+                    .filter(|e| filter.matches(&e.path, e.original_size, monitors))
+                    .count();
+                ui.weak(format!("{shown} of {}", entries.len()));
+            }
+        });
+        ui.add_space(3.0);
+    }
+
+    /// Thumbnails in fixed-size cells; only the rows in view are laid out.
+    fn show_image_grid(
+        &self,
+        ui: &mut egui::Ui,
+        entries: &[ImageEntry],
+    ) -> Option<(PathBuf, bool)> {
         let thumb_size = self.thumb_size;
+        let multiple_roots = self.gallery.folders().len() > 1;
         let mut clicked = None;
 
+        let visible: Vec<&ImageEntry> = entries.iter().filter(|e| self.is_visible(e)).collect();
+        let spacing = ui.spacing().item_spacing;
+        let columns = ((ui.available_width() + spacing.x) / (thumb_size + spacing.x)).max(1.0);
+        let columns = columns as usize;
+        let rows = visible.len().div_ceil(columns);
+
         egui::ScrollArea::vertical()
             .max_width(f32::INFINITY)
-            .show(ui, |ui| {
-                ui.horizontal_wrapped(|ui| {
-                    for (i, entry) in entries.iter().enumerate() {
-                        let response = ui.add(
-                            egui::Image::new(&entry.texture)
-                                .maintain_aspect_ratio(true)
-                                .fit_to_exact_size(egui::vec2(thumb_size, thumb_size))
-                                .sense(egui::Sense::click()),
-                        );
+            .auto_shrink([false, true])
+            .show_rows(ui, thumb_size, rows, |ui, row_range| {
+                let wanted_end = ((row_range.end + LOOKAHEAD_ROWS) * columns).min(visible.len());
+                let in_view = visible[row_range.start * columns..wanted_end]
+                    .iter()
+                    .map(|e| e.path.as_path());
+                // The selection row shows thumbnails too, wherever the grid is
+                // scrolled; both go in one request, as each replaces the last.
+                let selected = self.selected.items().iter().map(PathBuf::as_path);
+                let missing = self.gallery.missing_thumbnails(selected.chain(in_view));
+                if !missing.is_empty() {
+                    self.gallery.request_thumbnails(missing);
+                }
 
-                        if let Some(label) = self.selected.badge(i) {
-                            paint_selection_badge(ui, response.rect, label);
-                        }
+                for row in row_range {
+                    let start = row * columns;
+                    let end = (start + columns).min(visible.len());
+                    ui.horizontal(|ui| {
+                        for &entry in &visible[start..end] {
+                            let (cell, _) = ui.allocate_exact_size(
+                                egui::vec2(thumb_size, thumb_size),
+                                egui::Sense::hover(),
+                            );
+                            let [w, h] = entry.original_size;
+                            let rect = fit_in_cell(egui::vec2(w as f32, h as f32), cell);
+                            let id = egui::Id::new(("thumbnail", &entry.path));
+                            let response = ui.interact(rect, id, egui::Sense::click());
+                            let error = self.gallery.failures().get(&entry.path);
+                            match self.gallery.texture(&entry.path) {
+                                Some(texture) => egui::Image::new(texture).paint_at(ui, rect),
+                                None if error.is_some() => paint_error_tile(ui, rect),
+                                None => {
+                                    let color = ui.visuals().faint_bg_color;
+                                    ui.painter().rect_filled(rect, 2.0, color);
+                                }
+                            }
 
-                        if response.clicked() {
-                            let shift = ui.input(|i| i.modifiers.shift);
-                            clicked = Some((i, shift));
-                        }
+                            if let Some(label) = self.selected.badge(&entry.path) {
+                                paint_selection_badge(ui, rect, label);
+                            }
+                            if multiple_roots {
+                                paint_source_marker(ui, rect, entry.root);
+                            }
 
-                        response.on_hover_ui(|ui| {
-                            show_image_tooltip(ui, entry);
-                        });
-                    }
-                });
+                            if response.clicked() {
+                                let shift = ui.input(|i| i.modifiers.shift);
+                                clicked = Some((entry.path.clone(), shift));
+                            }
+
+                            response.on_hover_ui(|ui| {
+                                show_image_tooltip(ui, entry, error);
+                            });
+                        }
+                    });
+                }
             });
 
         clicked
     }
 }
 
+/// The largest rect with the aspect ratio of `size` centered in `cell`.
+fn fit_in_cell(size: egui::Vec2, cell: egui::Rect) -> egui::Rect {
+    let size = size.max(egui::vec2(1.0, 1.0));
+    let scale = (cell.width() / size.x).min(cell.height() / size.y);
+    egui::Rect::from_center_size(cell.center(), size * scale)
+}
+
+/// A folder as a selectable row, with its subfolders collapsed below it.
+fn show_folder(
+    ui: &mut egui::Ui,
+    folder: &Folder,
+    current: &Path,
+    chosen: &mut Option<PathBuf>,
+    default_open: bool,
+) {
+    let mut label = |ui: &mut egui::Ui| {
+        let text = format!("{} ({})", folder.name, folder.count);
+        if ui.selectable_label(folder.path == current, text).clicked() {
+            *chosen = Some(folder.path.clone());
+        }
+    };
+    if folder.children.is_empty() {
+        label(ui);
+        return;
+    }
+
+    let id = ui.make_persistent_id(&folder.path);
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, default_open)
+        .show_header(ui, label)
+        .body(|ui| {
+            for child in &folder.children {
+                show_folder(ui, child, current, chosen, false);
+            }
+        });
+}
+
 fn paint_selection_badge(ui: &egui::Ui, rect: egui::Rect, label: &str) {
     let center = rect.left_top() + egui::vec2(16.0, 16.0);
     let painter = ui.painter();
@@ -230,13 +660,58 @@ fn paint_selection_badge(ui: &egui::Ui, rect: egui::Rect, label: &str) {
     );
 }
 
-fn show_image_tooltip(ui: &mut egui::Ui, entry: &ImageEntry) {
-    let full_path = entry.texture.name();
-    let path = Path::new(&full_path);
-    let name = path
+/// Tag a thumbnail with the color of the library folder it comes from.
+fn paint_source_marker(ui: &egui::Ui, rect: egui::Rect, root: usize) {
+    let center = rect.left_bottom() + egui::vec2(10.0, -10.0);
+    let painter = ui.painter();
+    painter.circle_filled(center, 6.0, egui::Color32::from_black_alpha(180));
+    painter.circle_filled(center, 4.5, source_color(root));
+}
+
+/// A color per loaded library folder, repeating after a while.
+fn source_color(root: usize) -> egui::Color32 {
+    const COLORS: [egui::Color32; 6] = [
+        egui::Color32::from_rgb(0x4e, 0x9a, 0xf1),
+        egui::Color32::from_rgb(0xf2, 0x9e, 0x38),
+        egui::Color32::from_rgb(0x5c, 0xc8, 0x6b),
+        egui::Color32::from_rgb(0xe0, 0x5a, 0x6d),
+        egui::Color32::from_rgb(0xb0, 0x7c, 0xe8),
+        egui::Color32::from_rgb(0xe8, 0xd4, 0x4d),
+    ];
+    COLORS[root % COLORS.len()]
+}
+
+/// Mark the point of the image that stays visible when it gets cropped.
+fn paint_crop_marker(ui: &egui::Ui, rect: egui::Rect, crop: [f32; 2]) {
+    let center = rect.min + rect.size() * egui::vec2(crop[0], crop[1]);
+    let painter = ui.painter();
+    painter.circle_stroke(center, 6.0, egui::Stroke::new(3.0, egui::Color32::BLACK));
+    painter.circle_stroke(center, 6.0, egui::Stroke::new(1.5, egui::Color32::WHITE));
+}
+
+/// Stands in for a thumbnail that could not be decoded.
+fn paint_error_tile(ui: &egui::Ui, rect: egui::Rect) {
+    let painter = ui.painter();
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+    painter.text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        "⚠",
+        egui::FontId::proportional(24.0),
+        ui.visuals().warn_fg_color,
+    );
+}
+
+fn show_image_tooltip(ui: &mut egui::Ui, entry: &ImageEntry, error: Option<&String>) {
+    let name = entry
+        .path
         .file_name()
         .map(|n| n.to_string_lossy())
         .unwrap_or_default();
     let [w, h] = entry.original_size;
+    let full_path = entry.path.display();
     ui.label(format!("{name}\n{w} × {h}\n\n{full_path}"));
+    if let Some(error) = error {
+        ui.colored_label(ui.visuals().warn_fg_color, error);
+    }
 }
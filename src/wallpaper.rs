@@ -1,29 +1,103 @@
+use crate::backend::{self, Backend};
 use crate::monitors::Monitor;
+use crate::svg;
 use image::codecs::jpeg::JpegEncoder;
 use image::imageops::FilterType;
 use image::{DynamicImage, GenericImageView, RgbImage};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 const MAX_PREVIEW_WIDTH: u32 = 1024;
 
+/// An image placed on a monitor.
+#[derive(Debug, Clone)]
+pub struct Assignment {
+    pub path: PathBuf,
+    pub monitor: Monitor,
+    pub framing: Framing,
+}
+
+impl Assignment {
+    pub fn new(path: PathBuf, monitor: Monitor) -> Self {
+        Self {
+            path,
+            monitor,
+            framing: Framing::default(),
+        }
+    }
+}
+
+/// How an image is fitted into its monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Framing {
+    pub fit: Fit,
+    /// Part of the image kept when `Fit::Cover` crops it, from 0.0 (left/top) to 1.0 (right/bottom).
+    pub crop: [f32; 2],
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Self {
+            fit: Fit::Cover,
+            crop: [0.5, 0.5],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fit {
+    /// Fill the monitor and crop the overflow.
+    #[default]
+    Cover,
+    /// Show the whole image with black bars.
+    Contain,
+    /// Fill the monitor, ignoring the aspect ratio.
+    Stretch,
+}
+
+impl Fit {
+    pub const ALL: [Fit; 3] = [Fit::Cover, Fit::Contain, Fit::Stretch];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Fit::Cover => "Cover",
+            Fit::Contain => "Contain",
+            Fit::Stretch => "Stretch",
+        }
+    }
+}
+
 /// Like `compose`, but loads cached thumbnails and scales the canvas down.
-pub fn compose_preview(assignments: &[(PathBuf, Monitor)]) -> Result<DynamicImage, String> {
-    let canvas_w: u32 = assignments.iter().map(|(_, m)| m.x + m.width).max().unwrap_or(0);
-    let canvas_h: u32 = assignments.iter().map(|(_, m)| m.y + m.height).max().unwrap_or(0);
+pub fn compose_preview(assignments: &[Assignment]) -> Result<DynamicImage, String> {
+    let canvas_w: u32 = assignments
+        .iter()
+        .map(|a| a.monitor.x + a.monitor.width)
+        .max()
+        .unwrap_or(0);
+    let canvas_h: u32 = assignments
+        .iter()
+        .map(|a| a.monitor.y + a.monitor.height)
+        .max()
+        .unwrap_or(0);
 
     let scale = f64::from(MAX_PREVIEW_WIDTH).min(f64::from(canvas_w)) / f64::from(canvas_w);
     let pw = (f64::from(canvas_w) * scale).ceil() as u32;
     let ph = (f64::from(canvas_h) * scale).ceil() as u32;
 
     let mut tiles = Vec::new();
-    for (path, monitor) in assignments {
-        let img = crate::cache::load_dynamic(path)
-            .or_else(|| image::open(path).ok())
-            .ok_or_else(|| format!("failed to open {}", path.display()))?;
-        let tw = (f64::from(monitor.width) * scale).ceil() as u32;
-        let th = (f64::from(monitor.height) * scale).ceil() as u32;
-        tiles.push((cover_resize(&img, tw, th), monitor, scale));
+    for a in assignments {
+        let tw = (f64::from(a.monitor.width) * scale).ceil() as u32;
+        let th = (f64::from(a.monitor.height) * scale).ceil() as u32;
+        let tile = if svg::is_svg(&a.path) {
+            open_fitted(&a.path, tw, th, a.framing)?
+        } else {
+            let img = crate::cache::load_dynamic(&a.path)
+                .or_else(|| crate::loader::open(&a.path).ok())
+                .ok_or_else(|| format!("failed to open {}", a.path.display()))?;
+            fit_resize(&img, tw, th, a.framing)
+        };
+        tiles.push((tile, &a.monitor, scale));
     }
 
     let mut canvas = RgbImage::new(pw, ph);
@@ -37,25 +111,24 @@ pub fn compose_preview(assignments: &[(PathBuf, Monitor)]) -> Result<DynamicImag
 }
 
 /// Compose images to fill each monitor into a single canvas.
-pub fn compose(assignments: &[(PathBuf, Monitor)], log: &dyn Fn(&str)) -> Result<DynamicImage, String> {
+pub fn compose(assignments: &[Assignment], log: &dyn Fn(&str)) -> Result<DynamicImage, String> {
     let canvas_w: u32 = assignments
         .iter()
-        .map(|(_, m)| m.x + m.width)
+        .map(|a| a.monitor.x + a.monitor.width)
         .max()
         .unwrap_or(0);
     let canvas_h: u32 = assignments
         .iter()
-        .map(|(_, m)| m.y + m.height)
+        .map(|a| a.monitor.y + a.monitor.height)
         .max()
         .unwrap_or(0);
 
     let mut tiles = Vec::new();
-    for (path, monitor) in assignments {
-        let filename = path.file_name().unwrap_or_default().to_string_lossy();
-        log(&format!("Resizing {filename} for {}…", monitor.name));
-        let img = image::open(path)
-            .map_err(|e| format!("failed to open {}: {e}", path.display()))?;
-        tiles.push((cover_resize(&img, monitor.width, monitor.height), monitor));
+    for a in assignments {
+        let filename = a.path.file_name().unwrap_or_default().to_string_lossy();
+        log(&format!("Resizing {filename} for {}…", a.monitor.name));
+        let (w, h) = (a.monitor.width, a.monitor.height);
+        tiles.push((open_fitted(&a.path, w, h, a.framing)?, &a.monitor));
     }
 
     log("Composing canvas…");
@@ -72,36 +145,121 @@ pub fn compose(assignments: &[(PathBuf, Monitor)], log: &dyn Fn(&str)) -> Result
     Ok(DynamicImage::from(canvas))
 }
 
-/// Compose images to fill each monitor, save the result, and set it as the wallpaper.
-pub fn apply(assignments: &[(PathBuf, Monitor)], log: &dyn Fn(&str)) -> Result<(), String> {
-    let composed = compose(assignments, log)?;
+/// Fit images to each monitor and set them as the wallpaper: composed into one
+/// spanned image for gsettings, or one image per output for compositors that
+/// take those.
+pub fn apply(assignments: &[Assignment], log: &dyn Fn(&str)) -> Result<(), String> {
+    match Backend::detect() {
+        Backend::Gsettings => {
+            let composed = compose(assignments, log)?;
+
+            log("Saving wallpaper…");
+            let save_path = save_composed(&composed.to_rgb8())?;
+
+            log("Setting wallpaper…");
+            set_wallpaper(&save_path)?;
+        }
+        backend => apply_per_output(backend, assignments, log)?,
+    }
+
+    let paths: Vec<&Path> = assignments.iter().map(|a| a.path.as_path()).collect();
+    crate::history::record(&paths);
+    Ok(())
+}
+
+/// Resize every image to its output's physical resolution and hand them to
+/// `backend` one by one, mirrors included.
+fn apply_per_output(
+    backend: Backend,
+    assignments: &[Assignment],
+    log: &dyn Fn(&str),
+) -> Result<(), String> {
+    let dir = dirs_data().join("nitrohydra/outputs");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("failed to create cache dir: {e}"))?;
+
+    let mut outputs = Vec::new();
+    for a in assignments {
+        let filename = a.path.file_name().unwrap_or_default().to_string_lossy();
+        log(&format!("Resizing {filename} for {}…", a.monitor.name));
+        let (w, h) = a.monitor.physical_size();
 
-    log("Saving wallpaper…");
-    let save_path = save_composed(&composed.to_rgb8())?;
+        let file = dir.join(format!("{}.jpg", a.monitor.connector));
+        save_jpeg(&open_fitted(&a.path, w, h, a.framing)?, &file)?;
+        outputs.extend(outputs_of(&a.monitor, &file));
+    }
 
     log("Setting wallpaper…");
-    set_wallpaper(&save_path)
+    backend::set_outputs(backend, &outputs)
+}
+
+/// The backend outputs showing `monitor`, mirrors included, each set to `file`.
+/// They go by connector, whatever label the overrides give the monitor.
+fn outputs_of(monitor: &Monitor, file: &Path) -> Vec<(String, PathBuf)> {
+    std::iter::once(&monitor.connector)
+        .chain(&monitor.mirrors)
+        .map(|output| (output.clone(), file.to_path_buf()))
+        .collect()
+}
+
+/// Open the image at `path` fitted into the target dimensions. SVGs are
+/// rasterized straight at that size instead of being resized.
+fn open_fitted(
+    path: &Path,
+    target_w: u32,
+    target_h: u32,
+    framing: Framing,
+) -> Result<RgbImage, String> {
+    let fitted = if svg::is_svg(path) {
+        svg::load(path).and_then(|tree| svg::render_fit(&tree, target_w, target_h, framing))
+    } else {
+        crate::loader::open(path).map(|img| fit_resize(&img, target_w, target_h, framing))
+    };
+    fitted.map_err(|e| format!("failed to open {}: {e}", path.display()))
 }
 
-/// Resize image to fully cover target dimensions (no letterboxing), then center-crop.
-fn cover_resize(img: &DynamicImage, target_w: u32, target_h: u32) -> RgbImage {
+/// Resize image into the target dimensions according to `framing`.
+fn fit_resize(img: &DynamicImage, target_w: u32, target_h: u32, framing: Framing) -> RgbImage {
     let (src_w, src_h) = img.dimensions();
 
-    // Scale factor: pick the larger scale so the image fully covers the target
     let scale_w = f64::from(target_w) / f64::from(src_w);
     let scale_h = f64::from(target_h) / f64::from(src_h);
-    let scale = scale_w.max(scale_h);
 
-    let scaled_w = (f64::from(src_w) * scale).ceil() as u32;
-    let scaled_h = (f64::from(src_h) * scale).ceil() as u32;
+    match framing.fit {
+        Fit::Cover => {
+            // Pick the larger scale so the image fully covers the target
+            let scale = scale_w.max(scale_h);
+            let scaled_w = (f64::from(src_w) * scale).ceil() as u32;
+            let scaled_h = (f64::from(src_h) * scale).ceil() as u32;
+
+            let resized = img.resize_exact(scaled_w, scaled_h, FilterType::CatmullRom);
+
+            // Crop to target dimensions around the chosen point
+            let [cx, cy] = framing.crop.map(|c| f64::from(c.clamp(0.0, 1.0)));
+            let crop_x = (f64::from(scaled_w.saturating_sub(target_w)) * cx).round() as u32;
+            let crop_y = (f64::from(scaled_h.saturating_sub(target_h)) * cy).round() as u32;
 
-    let resized = img.resize_exact(scaled_w, scaled_h, FilterType::CatmullRom);
+            resized
+                .crop_imm(crop_x, crop_y, target_w, target_h)
+                .to_rgb8()
+        }
+        Fit::Contain => {
+            // Pick the smaller scale so the whole image fits, then center it
+            let scale = scale_w.min(scale_h);
+            let scaled_w = ((f64::from(src_w) * scale).round() as u32).clamp(1, target_w);
+            let scaled_h = ((f64::from(src_h) * scale).round() as u32).clamp(1, target_h);
 
-    // Center-crop to target dimensions
-    let crop_x = (scaled_w.saturating_sub(target_w)) / 2;
-    let crop_y = (scaled_h.saturating_sub(target_h)) / 2;
+            let resized = img.resize_exact(scaled_w, scaled_h, FilterType::CatmullRom);
 
-    resized.crop_imm(crop_x, crop_y, target_w, target_h).to_rgb8()
+            let mut tile = RgbImage::new(target_w, target_h);
+            let x = (target_w - scaled_w) / 2;
+            let y = (target_h - scaled_h) / 2;
+            image::imageops::overlay(&mut tile, &resized.to_rgb8(), i64::from(x), i64::from(y));
+            tile
+        }
+        Fit::Stretch => img
+            .resize_exact(target_w, target_h, FilterType::CatmullRom)
+            .to_rgb8(),
+    }
 }
 
 fn save_composed(canvas: &RgbImage) -> Result<PathBuf, String> {
@@ -109,8 +267,14 @@ fn save_composed(canvas: &RgbImage) -> Result<PathBuf, String> {
     std::fs::create_dir_all(&cache_dir)
         .map_err(|e| format!("failed to create cache dir: {e}"))?;
 
-    let tmp_path = cache_dir.join("_composed.tmp");
     let final_path = cache_dir.join("_composed.jpg");
+    save_jpeg(canvas, &final_path)?;
+    Ok(final_path)
+}
+
+/// Encode `canvas` as JPEG into a temporary file next to `path`, then move it into place.
+pub fn save_jpeg(canvas: &RgbImage, path: &Path) -> Result<(), String> {
+    let tmp_path = path.with_extension("tmp");
 
     let file = std::fs::File::create(&tmp_path)
         .map_err(|e| format!("failed to create wallpaper file: {e}"))?;
@@ -119,10 +283,7 @@ fn save_composed(canvas: &RgbImage) -> Result<PathBuf, String> {
         .write_with_encoder(encoder)
         .map_err(|e| format!("failed to save wallpaper: {e}"))?;
 
-    std::fs::rename(&tmp_path, &final_path)
-        .map_err(|e| format!("failed to rename wallpaper file: {e}"))?;
-
-    Ok(final_path)
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("failed to rename wallpaper file: {e}"))
 }
 
 const SCHEMAS: &[&str] = &[
@@ -131,7 +292,9 @@ const SCHEMAS: &[&str] = &[
     "org.mate.background",
 ];
 
-fn set_wallpaper(path: &Path) -> Result<(), String> {
+/// Point the desktop background at `path`, spanned across all monitors.
+/// `path` may be an image or a GNOME background XML slideshow.
+pub fn set_wallpaper(path: &Path) -> Result<(), String> {
     let uri = format!("file://{}", path.display());
     let mut any_ok = false;
     for schema in SCHEMAS {
@@ -161,7 +324,7 @@ fn gsettings_set(schema: &str, key: &str, value: &str) -> Result<(), String> {
     Ok(())
 }
 
-fn dirs_data() -> PathBuf {
+pub fn dirs_data() -> PathBuf {
     std::env::var_os("XDG_DATA_HOME")
         .map(PathBuf::from)
         .unwrap_or_else(|| {
@@ -170,3 +333,35 @@ fn dirs_data() -> PathBuf {
             home
         })
 }
+
+pub fn dirs_config() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let mut home = PathBuf::from(std::env::var_os("HOME").unwrap_or_default());
+            home.push(".config");
+            home
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_go_by_connector_when_renamed() {
+        let monitor = Monitor {
+            name: "TV".into(),
+            mirrors: vec!["DP-2".into()],
+            ..Monitor::test("HDMI-A-1", 1920, 1080, 0)
+        };
+        let file = Path::new("/tmp/HDMI-A-1.jpg");
+        assert_eq!(
+            outputs_of(&monitor, file),
+            [
+                ("HDMI-A-1".to_string(), file.to_path_buf()),
+                ("DP-2".to_string(), file.to_path_buf()),
+            ]
+        );
+    }
+}
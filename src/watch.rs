@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::randr::{ConnectionExt as _, NotifyMask};
+use x11rb::rust_connection::RustConnection;
+
+use crate::{layouts, monitors, slideshow, wallpaper};
+
+/// How often the monitor list is re-read when RandR events are unavailable.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Outputs change in bursts while a monitor is (un)plugged; wait for them to settle.
+const SETTLE_DELAY: Duration = Duration::from_secs(1);
+const TICK: Duration = Duration::from_millis(250);
+
+/// Recompose the remembered wallpaper whenever the monitor layout changes,
+/// until SIGTERM or SIGINT.
+pub fn run() -> Result<(), String> {
+    let stop = slideshow::stop_flag()?;
+    let mut last = monitors::arrangement(&monitors::detect()?);
+
+    match randr_connection() {
+        Ok(conn) => {
+            eprintln!("Watching RandR output changes…");
+            watch_events(&conn, &stop, &mut last);
+        }
+        Err(e) => {
+            eprintln!("RandR events unavailable ({e}), polling xrandr instead…");
+            watch_polling(&stop, &mut last);
+        }
+    }
+
+    eprintln!("Watch stopped.");
+    Ok(())
+}
+
+fn randr_connection() -> Result<RustConnection, String> {
+    let (conn, screen) = x11rb::connect(None).map_err(|e| e.to_string())?;
+    let root = conn.setup().roots[screen].root;
+    conn.randr_query_version(1, 2)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?;
+    let mask = NotifyMask::SCREEN_CHANGE | NotifyMask::OUTPUT_CHANGE | NotifyMask::CRTC_CHANGE;
+    conn.randr_select_input(root, mask)
+        .map_err(|e| e.to_string())?;
+    conn.flush().map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn watch_events(conn: &RustConnection, stop: &AtomicBool, last: &mut String) {
+    let mut changed_at = None;
+    while !stop.load(Ordering::Relaxed) {
+        loop {
+            match conn.poll_for_event() {
+                Ok(Some(Event::RandrScreenChangeNotify(_) | Event::RandrNotify(_))) => {
+                    changed_at = Some(Instant::now());
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("error: lost X connection ({e}), polling xrandr instead…");
+                    return watch_polling(stop, last);
+                }
+            }
+        }
+        if changed_at.is_some_and(|t| t.elapsed() >= SETTLE_DELAY) {
+            changed_at = None;
+            check(last);
+        }
+        std::thread::sleep(TICK);
+    }
+}
+
+fn watch_polling(stop: &AtomicBool, last: &mut String) {
+    while !stop.load(Ordering::Relaxed) {
+        slideshow::sleep(POLL_INTERVAL, stop);
+        check(last);
+    }
+}
+
+/// Re-detect monitors and recompose if the layout differs from `last`, down to
+/// positions, so swapping two same-sized outputs counts.
+fn check(last: &mut String) {
+    let monitors = match monitors::detect() {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return;
+        }
+    };
+    let arrangement = monitors::arrangement(&monitors);
+    if arrangement == *last {
+        return;
+    }
+    eprintln!("Monitor layout changed: {arrangement}");
+    *last = arrangement;
+
+    match layouts::for_monitors(&monitors) {
+        Ok(Some(assignments)) => {
+            if let Err(e) = wallpaper::apply(&assignments, &|msg| eprintln!("{msg}")) {
+                eprintln!("error: {e}");
+            }
+        }
+        Ok(None) => eprintln!("No remembered wallpaper to recompose."),
+        Err(e) => eprintln!("error: {e}"),
+    }
+}